@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use alloy::rpc::types::{Header, Log};
+use harpoon::config::EngineLoader;
+use harpoon::executor::telegram_message::{Message, MessageBuilder};
+use harpoon::{IActionSubmitter, IStrategy, submit_action};
+use serde::Deserialize;
+
+#[tokio::main]
+async fn main() {
+    let engine = EngineLoader::new()
+        .on_block(Event::Block)
+        .on_log(Event::Log)
+        .send_messages(|action| match action {
+            Action::Notify(message) => Some(message),
+        })
+        .register_strategy("echo", |params| {
+            let params: EchoParams = serde_json::from_value(params.clone())?;
+            let bot_token = std::env::var(&params.bot_token_env)?;
+
+            Ok(Box::new(EchoStrategy {
+                bot_token,
+                chat_id: params.chat_id,
+            }))
+        })
+        .load_file("examples/engine.toml")
+        .await
+        .expect("fail to load engine");
+
+    engine.run_and_join().await.unwrap()
+}
+
+#[derive(Deserialize)]
+struct EchoParams {
+    bot_token_env: String,
+    chat_id: String,
+}
+
+pub struct EchoStrategy {
+    bot_token: String,
+    chat_id: String,
+}
+
+#[async_trait::async_trait]
+impl IStrategy<Event, Action> for EchoStrategy {
+    async fn process_event(&mut self, event: Event, submitter: Arc<dyn IActionSubmitter<Action>>) {
+        let text = match event {
+            Event::Block(block) => format!("block {}", block.number),
+            Event::Log(log) => format!("WETH transfer in {:?}", log.transaction_hash),
+        };
+
+        let message = MessageBuilder::new()
+            .bot_token(&self.bot_token)
+            .chat_id(&self.chat_id)
+            .text(text)
+            .build();
+
+        submit_action!(submitter, Action::Notify, message);
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+enum Event {
+    Block(Header),
+    Log(Log),
+}
+
+#[derive(Debug, Clone)]
+enum Action {
+    Notify(Message),
+}
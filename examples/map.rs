@@ -66,6 +66,7 @@ impl<T: Debug + Send + Sync> IExecutor<T> for EchoExecutor<T> {
     }
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 enum Event {
     Block(Header),
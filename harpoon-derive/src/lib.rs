@@ -0,0 +1,185 @@
+//! Derive macros for harpoon's event and action enums, re-exported by `harpoon` with the `derive` feature.
+
+use std::collections::HashMap;
+
+use heck::ToSnakeCase;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Ident, Type, parse_macro_input, spanned::Spanned};
+
+/// Wire an event enum to collectors. For every variant holding a single value, generates:
+///
+/// - `From<Payload>` for the enum, if no other variant holds the same type;
+/// - `as_<variant>(&self) -> Option<&Payload>` and `into_<variant>(self) -> Option<Payload>`;
+/// - a marker type named after the variant, in a module named after the enum in snake case, implementing
+///   `harpoon::IVariant` for `Engine::add_collector_for` and `Engine::add_strategy_for`.
+///
+/// ```ignore
+/// #[derive(Debug, Clone, HarpoonEvent)]
+/// enum Event {
+///     Block(Header),
+///     Transaction(Transaction),
+/// }
+///
+/// engine.add_collector_for::<event::Block>(block_collector);
+/// ```
+#[proc_macro_derive(HarpoonEvent)]
+pub fn derive_harpoon_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+/// Wire an action enum to executors. Generates the same items as [`HarpoonEvent`]; the marker types are meant for
+/// `Engine::add_executor_for`:
+///
+/// ```ignore
+/// #[derive(Debug, Clone, HarpoonAction)]
+/// enum Action {
+///     SendTx(TransactionRequest),
+///     Message(String),
+/// }
+///
+/// engine.add_executor_for::<action::SendTx>(sender);
+/// submitter.submit(tx.into());
+/// ```
+#[proc_macro_derive(HarpoonAction)]
+pub fn derive_harpoon_action(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+struct Variant<'a> {
+    ident: &'a Ident,
+    payload: &'a Type,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(input.span(), "only enums can be derived"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            input.generics.span(),
+            "generic enums are not supported",
+        ));
+    }
+
+    // Variants that don't hold exactly one value have nothing to extract.
+    let variants: Vec<Variant> = data
+        .variants
+        .iter()
+        .filter_map(|variant| match &variant.fields {
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Some(Variant {
+                ident: &variant.ident,
+                payload: &fields.unnamed[0].ty,
+            }),
+            _ => None,
+        })
+        .collect();
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let module = format_ident!("{}", name.to_string().to_snake_case());
+
+    let mut payload_counts: HashMap<String, usize> = HashMap::new();
+    for variant in &variants {
+        let payload = variant.payload;
+        *payload_counts.entry(quote!(#payload).to_string()).or_default() += 1;
+    }
+
+    let from_impls = variants
+        .iter()
+        .filter(|variant| {
+            let payload = variant.payload;
+            payload_counts[&quote!(#payload).to_string()] == 1
+        })
+        .map(|Variant { ident, payload }| {
+            quote! {
+                impl ::core::convert::From<#payload> for #name {
+                    fn from(value: #payload) -> Self {
+                        Self::#ident(value)
+                    }
+                }
+            }
+        });
+
+    let extractors = variants.iter().map(|Variant { ident, payload }| {
+        let snake = ident.to_string().to_snake_case();
+        let as_fn = format_ident!("as_{}", snake);
+        let into_fn = format_ident!("into_{}", snake);
+
+        quote! {
+            #[allow(unreachable_patterns)]
+            #vis fn #as_fn(&self) -> ::core::option::Option<&#payload> {
+                match self {
+                    Self::#ident(value) => ::core::option::Option::Some(value),
+                    _ => ::core::option::Option::None,
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            #vis fn #into_fn(self) -> ::core::option::Option<#payload> {
+                match self {
+                    Self::#ident(value) => ::core::option::Option::Some(value),
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    });
+
+    let markers = variants.iter().map(|Variant { ident, .. }| {
+        let doc = format!("Marker for [`{name}::{ident}`](super::{name}::{ident}).");
+        quote! {
+            #[doc = #doc]
+            #[derive(Debug, Clone, Copy)]
+            pub struct #ident;
+        }
+    });
+
+    let variant_impls = variants.iter().map(|Variant { ident, payload }| {
+        quote! {
+            #[allow(unreachable_patterns)]
+            impl ::harpoon::IVariant<#name> for #module::#ident {
+                type Payload = #payload;
+
+                fn wrap(payload: #payload) -> #name {
+                    #name::#ident(payload)
+                }
+
+                fn extract(value: #name) -> ::core::option::Option<#payload> {
+                    match value {
+                        #name::#ident(payload) => ::core::option::Option::Some(payload),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+
+                fn extract_ref(value: &#name) -> ::core::option::Option<&#payload> {
+                    match value {
+                        #name::#ident(payload) => ::core::option::Option::Some(payload),
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+        }
+    });
+
+    let module_doc = format!("Variants of [`{name}`](super::{name}), generated by its derive.");
+
+    Ok(quote! {
+        #(#from_impls)*
+
+        #[allow(dead_code)]
+        impl #name {
+            #(#extractors)*
+        }
+
+        #[doc = #module_doc]
+        #[allow(dead_code)]
+        #vis mod #module {
+            #(#markers)*
+        }
+
+        #(#variant_impls)*
+    })
+}
@@ -0,0 +1,119 @@
+use std::{
+    ops::Range,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use tracing::warn;
+
+/// How many skipped blocks a collector fetches by default before resuming live emission.
+pub(crate) const DEFAULT_MAX_BACKFILL: u64 = 128;
+
+/// The blocks missing between the last emitted block and `number`, oldest first, keeping only the last
+/// `max_backfill` of them.
+pub(crate) fn backfill_range(last: Option<u64>, number: u64, max_backfill: u64) -> Range<u64> {
+    let Some(last) = last else {
+        return number..number;
+    };
+
+    let first_missing = last + 1;
+    if number <= first_missing {
+        return number..number;
+    }
+
+    let start = first_missing.max(number.saturating_sub(max_backfill));
+    if start > first_missing {
+        warn!(
+            from = first_missing,
+            to = start - 1,
+            max_backfill,
+            "gap exceeds max backfill, skipping blocks"
+        );
+    }
+    start..number
+}
+
+/// Remembers the last block a collector emitted, across resubscriptions, to find the blocks it skipped.
+pub(crate) struct BlockGaps {
+    last: Mutex<Option<u64>>,
+    max_backfill: u64,
+    /// Whether the collector is still catching up with a checkpoint, whose gap is backfilled whatever its size.
+    resumed: AtomicBool,
+}
+
+impl BlockGaps {
+    pub(crate) fn new(max_backfill: u64) -> Self {
+        Self {
+            last: Mutex::new(None),
+            max_backfill,
+            resumed: AtomicBool::new(false),
+        }
+    }
+
+    /// Blocks to fetch before emitting `number`.
+    pub(crate) fn missing_before(&self, number: u64) -> Range<u64> {
+        let last = *self.last.lock().unwrap();
+
+        if !self.resumed.load(Ordering::Relaxed) {
+            return backfill_range(last, number, self.max_backfill);
+        }
+
+        let missing = backfill_range(last, number, u64::MAX);
+        if missing.is_empty() {
+            self.resumed.store(false, Ordering::Relaxed);
+        }
+        missing
+    }
+
+    /// Pick up after `number`, a checkpoint from a previous run, unless blocks were emitted since. All the blocks
+    /// after it are backfilled, regardless of `max_backfill`.
+    pub(crate) fn resume_from(&self, number: u64) {
+        let mut last = self.last.lock().unwrap();
+        if last.is_none() {
+            *last = Some(number);
+            self.resumed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn emitted(&self, number: u64) {
+        *self.last.lock().unwrap() = Some(number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockGaps, backfill_range};
+
+    #[test]
+    fn test_backfill_range() {
+        assert_eq!(backfill_range(None, 10, 5), 10..10);
+        assert_eq!(backfill_range(Some(9), 10, 5), 10..10);
+        assert_eq!(backfill_range(Some(7), 10, 5), 8..10);
+        // Replacements and older blocks have nothing to backfill.
+        assert_eq!(backfill_range(Some(10), 10, 5), 10..10);
+        assert_eq!(backfill_range(Some(12), 10, 5), 10..10);
+        // Only the last `max_backfill` missing blocks are fetched.
+        assert_eq!(backfill_range(Some(1), 20, 5), 15..20);
+        assert_eq!(backfill_range(Some(1), 20, 0), 20..20);
+    }
+
+    #[test]
+    fn test_checkpoint_gap_is_backfilled_whole() {
+        let gaps = BlockGaps::new(5);
+        gaps.resume_from(1);
+        assert_eq!(gaps.missing_before(20), 2..20);
+
+        // Once caught up, gaps are capped again.
+        gaps.emitted(19);
+        assert_eq!(gaps.missing_before(20), 20..20);
+        gaps.emitted(20);
+        assert_eq!(gaps.missing_before(40), 35..40);
+
+        // A checkpoint is ignored once blocks were emitted.
+        gaps.emitted(40);
+        gaps.resume_from(3);
+        assert_eq!(gaps.missing_before(41), 41..41);
+    }
+}
@@ -1,16 +1,44 @@
 use alloy::{providers::Provider, rpc::types::Header};
 use async_trait::async_trait;
+use futures::StreamExt;
 use std::sync::Arc;
+use tracing::{error, warn};
 
+use super::{
+    backfill::{BlockGaps, DEFAULT_MAX_BACKFILL},
+    checkpoint::{Checkpoint, ICheckpointStore},
+};
 use crate::{CollectorStream, ICollector};
 
+/// Emits the header of every new block. Blocks skipped by the subscription, or missed while resubscribing, are
+/// fetched and emitted in order before the next live block; if one of them can't be fetched, the live block is held
+/// back and the gap is fetched again when the next one arrives.
 pub struct BlockCollector {
     provider: Arc<dyn Provider>,
+    gaps: BlockGaps,
+    checkpoint: Option<Checkpoint>,
 }
 
 impl BlockCollector {
     pub fn new(provider: Arc<dyn Provider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            gaps: BlockGaps::new(DEFAULT_MAX_BACKFILL),
+            checkpoint: None,
+        }
+    }
+
+    /// Backfill at most `max_backfill` missed blocks, the most recent ones, defaults to 128.
+    pub fn with_max_backfill(mut self, max_backfill: u64) -> Self {
+        self.gaps = BlockGaps::new(max_backfill);
+        self
+    }
+
+    /// Save the last emitted block to `store` under `key`. On start, all the blocks after the one saved by a previous
+    /// run are backfilled, regardless of `max_backfill`.
+    pub fn with_checkpoint(mut self, store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        self.checkpoint = Some(Checkpoint::new(store, key));
+        self
     }
 }
 
@@ -20,9 +48,58 @@ impl ICollector<Header> for BlockCollector {
         "Block Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Header>> {
-        let stream = self.provider.subscribe_blocks().await?;
+        if let Some(checkpoint) = &self.checkpoint
+            && let Some(block) = checkpoint.load().await
+        {
+            self.gaps.resume_from(block);
+        }
+
+        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
+
+        let stream = async_stream::stream! {
+            while let Some(header) = stream.next().await {
+                // On failure, the blocks from the missing one on are fetched again before the next live block.
+                let mut backfilled = true;
+                for number in self.gaps.missing_before(header.number) {
+                    match self.provider.get_block_by_number(number.into()).await {
+                        Ok(Some(block)) => {
+                            self.gaps.emitted(number);
+                            yield block.header;
+                            if let Some(checkpoint) = &self.checkpoint {
+                                checkpoint.save(number).await;
+                            }
+                        }
+                        Ok(None) => {
+                            warn!("missed block not found: {}", number);
+                            backfilled = false;
+                            break;
+                        }
+                        Err(e) => {
+                            error!("fail to backfill block: {:#}, block number: {}", e, number);
+                            backfilled = false;
+                            break;
+                        }
+                    }
+                }
+
+                if !backfilled {
+                    continue;
+                }
+
+                let number = header.number;
+                self.gaps.emitted(number);
+                yield header;
+                if let Some(checkpoint) = &self.checkpoint {
+                    checkpoint.save(number).await;
+                }
+            }
+        };
 
-        Ok(Box::pin(stream.into_stream()))
+        Ok(Box::pin(stream))
     }
 }
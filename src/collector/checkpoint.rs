@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+#[cfg(feature = "evm")]
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use eyre::{Result, WrapErr};
+#[cfg(feature = "evm")]
+use tracing::warn;
+
+use crate::engine::file_stem;
+
+/// Where collectors keep the last block they emitted, by key, to resume from it after a restart.
+#[async_trait]
+pub trait ICheckpointStore: Send + Sync {
+    async fn load(&self, key: &str) -> Result<Option<u64>>;
+
+    async fn save(&self, key: &str, block: u64) -> Result<()>;
+}
+
+/// Keeps each checkpoint as a block number in a text file of `dir`, replaced atomically on save.
+pub struct FileCheckpointStore {
+    dir: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.checkpoint", file_stem(key)))
+    }
+}
+
+#[async_trait]
+impl ICheckpointStore for FileCheckpointStore {
+    async fn load(&self, key: &str) -> Result<Option<u64>> {
+        let path = self.path(key);
+
+        let content = match tokio::task::spawn_blocking(move || std::fs::read_to_string(path)).await? {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).wrap_err_with(|| format!("fail to read checkpoint of {key}")),
+        };
+
+        let block = content
+            .trim()
+            .parse()
+            .wrap_err_with(|| format!("invalid checkpoint of {key}"))?;
+        Ok(Some(block))
+    }
+
+    async fn save(&self, key: &str, block: u64) -> Result<()> {
+        let path = self.path(key);
+        let dir = self.dir.clone();
+
+        tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&dir)?;
+            let tmp = path.with_extension("checkpoint.tmp");
+            std::fs::write(&tmp, block.to_string())?;
+            std::fs::rename(tmp, path)
+        })
+        .await?
+        .wrap_err_with(|| format!("fail to save checkpoint of {key}"))
+    }
+}
+
+/// The checkpoint of one collector. Store failures are logged rather than ending the stream.
+#[cfg(feature = "evm")]
+pub(crate) struct Checkpoint {
+    store: Arc<dyn ICheckpointStore>,
+    key: String,
+}
+
+#[cfg(feature = "evm")]
+impl Checkpoint {
+    pub(crate) fn new(store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        Self { store, key: key.into() }
+    }
+
+    pub(crate) async fn load(&self) -> Option<u64> {
+        match self.store.load(&self.key).await {
+            Ok(block) => block,
+            Err(e) => {
+                warn!(key = self.key, "fail to load collector checkpoint: {e:#}");
+                None
+            }
+        }
+    }
+
+    pub(crate) async fn save(&self, block: u64) {
+        if let Err(e) = self.store.save(&self.key, block).await {
+            warn!(key = self.key, "fail to save collector checkpoint: {e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileCheckpointStore, ICheckpointStore};
+
+    #[tokio::test]
+    async fn test_file_checkpoint_store() {
+        let dir = std::env::temp_dir().join(format!("harpoon-checkpoints-{}", std::process::id()));
+        let store = FileCheckpointStore::new(&dir);
+
+        assert_eq!(store.load("blocks/mainnet").await.unwrap(), None);
+        store.save("blocks/mainnet", 100).await.unwrap();
+        store.save("blocks/mainnet", 101).await.unwrap();
+        assert_eq!(store.load("blocks/mainnet").await.unwrap(), Some(101));
+        assert_eq!(store.load("logs").await.unwrap(), None);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    hash::Hash,
+    sync::Mutex,
+};
+
+use alloy::{
+    primitives::B256,
+    rpc::types::{Header, eth::Log},
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use tracing::{debug, error, warn};
+
+use super::{ChainEvent, IBlock};
+use crate::{CollectorStream, ICollector};
+
+/// An item tied to the block it was included in, that can be held back until that block is confirmed.
+pub trait IConfirmable: Send + Sync + 'static {
+    /// Number and hash of the block the item belongs to. Items without one, such as pending logs, are let through
+    /// right away.
+    fn block(&self) -> Option<(u64, B256)>;
+
+    /// Whether the item retracts an earlier one, like a log with `removed: true` after a reorg.
+    fn is_removal(&self) -> bool {
+        false
+    }
+
+    /// Identifies the item, to match a removal with the item it retracts and skip items emitted twice.
+    type Key: Eq + Hash + Send + Sync;
+
+    fn key(&self) -> Self::Key;
+}
+
+impl IConfirmable for Log {
+    fn block(&self) -> Option<(u64, B256)> {
+        Some((self.block_number?, self.block_hash?))
+    }
+
+    fn is_removal(&self) -> bool {
+        self.removed
+    }
+
+    type Key = (Option<B256>, Option<u64>);
+
+    fn key(&self) -> Self::Key {
+        (self.block_hash, self.log_index)
+    }
+}
+
+impl IConfirmable for (Header, Vec<Log>) {
+    fn block(&self) -> Option<(u64, B256)> {
+        Some((self.0.inner.number, self.0.hash))
+    }
+
+    type Key = B256;
+
+    fn key(&self) -> Self::Key {
+        self.0.hash
+    }
+}
+
+/// Holds back the items of another collector, such as a [`LogCollector`] or a [`LogsInBlockCollector`], until
+/// `confirmations` blocks have been built on top of the block they belong to. With 0 confirmations, items are only
+/// dropped if their block was already orphaned.
+///
+/// The chain is followed through `chain`, usually a [`ReorgCollector`] over the provider's new blocks: items of
+/// blocks a reorg removes are dropped, and so are items whose block turns out not to be canonical by the time they
+/// are confirmed. A log with `removed: true` drops the pending log it retracts and is not emitted itself. Pending
+/// items are kept across resubscriptions.
+///
+/// ```ignore
+/// let chain = ReorgCollector::new(Box::new(BlockCollector::new(provider.clone())), provider.clone());
+/// let logs = LogCollector::new(provider, filter);
+/// let transfers = ConfirmationCollector::new(Box::new(logs), Box::new(chain), 12);
+/// engine.add_collector(map_collector!(transfers, Event::ConfirmedTransfer));
+/// ```
+///
+/// [`LogCollector`]: super::LogCollector
+/// [`LogsInBlockCollector`]: super::LogsInBlockCollector
+/// [`ReorgCollector`]: super::ReorgCollector
+pub struct ConfirmationCollector<T: IConfirmable, B = Header> {
+    inner: Box<dyn ICollector<T>>,
+    chain: Box<dyn ICollector<ChainEvent<B>>>,
+    pending: Mutex<Confirmations<T>>,
+}
+
+impl<T: IConfirmable, B> ConfirmationCollector<T, B> {
+    pub fn new(
+        collector: Box<dyn ICollector<T>>,
+        chain: Box<dyn ICollector<ChainEvent<B>>>,
+        confirmations: u64,
+    ) -> Self {
+        Self {
+            inner: collector,
+            chain,
+            pending: Mutex::new(Confirmations::new(confirmations)),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: IConfirmable, B: IBlock> ICollector<T> for ConfirmationCollector<T, B> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, T>> {
+        let mut chain = self.chain.get_event_stream().await?;
+        let mut items = self.inner.get_event_stream().await?;
+
+        let stream = async_stream::stream! {
+            loop {
+                let released = tokio::select! {
+                    item = items.next() => match item {
+                        Some(item) => self.pending.lock().unwrap().on_item(item),
+                        None => break,
+                    },
+                    event = chain.next() => match event {
+                        Some(event) => self.pending.lock().unwrap().on_chain_event(&event),
+                        None => {
+                            error!("block subscription ended");
+                            break;
+                        }
+                    },
+                };
+
+                for item in released {
+                    yield item;
+                }
+            }
+
+            let pending = self.pending.lock().unwrap().pending.len();
+            if pending > 0 {
+                warn!(pending, "stream ended with items awaiting confirmation, kept until resubscribed");
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Items waiting for their block to be confirmed, in the order they arrived.
+struct Confirmations<T: IConfirmable> {
+    confirmations: u64,
+    pending: VecDeque<T>,
+    /// Keys of the pending items.
+    keys: HashSet<T::Key>,
+    /// Hashes of the recent canonical blocks, by number.
+    canonical: BTreeMap<u64, B256>,
+    head: Option<u64>,
+}
+
+impl<T: IConfirmable> Confirmations<T> {
+    fn new(confirmations: u64) -> Self {
+        Self {
+            confirmations,
+            pending: VecDeque::new(),
+            keys: HashSet::new(),
+            canonical: BTreeMap::new(),
+            head: None,
+        }
+    }
+
+    fn on_item(&mut self, item: T) -> Vec<T> {
+        if item.is_removal() {
+            let key = item.key();
+            if self.keys.remove(&key) {
+                self.pending.retain(|pending| pending.key() != key);
+            }
+            return vec![];
+        }
+
+        if item.block().is_none() {
+            return vec![item];
+        }
+
+        // Emitted again by a resubscribed collector.
+        if !self.keys.insert(item.key()) {
+            return vec![];
+        }
+
+        self.pending.push_back(item);
+        self.release()
+    }
+
+    fn on_chain_event<B: IBlock>(&mut self, event: &ChainEvent<B>) -> Vec<T> {
+        let added = match event {
+            ChainEvent::NewBlock(block) => std::slice::from_ref(block),
+            ChainEvent::Reorg { removed, added } => {
+                let orphaned = |(number, hash)| {
+                    removed
+                        .iter()
+                        .any(|block| (block.number(), block.hash()) == (number, hash))
+                };
+                let before = self.pending.len();
+                let keys = &mut self.keys;
+                self.pending.retain(|item| {
+                    let keep = item.block().is_none_or(|block| !orphaned(block));
+                    if !keep {
+                        keys.remove(&item.key());
+                    }
+                    keep
+                });
+                debug!(
+                    dropped = before - self.pending.len(),
+                    "dropped items of orphaned blocks"
+                );
+
+                for block in removed {
+                    self.canonical.remove(&block.number());
+                }
+                added.as_slice()
+            }
+        };
+
+        for block in added {
+            self.canonical.insert(block.number(), block.hash());
+        }
+        self.head = self.canonical.last_key_value().map(|(number, _)| *number);
+
+        // Keep the hashes of blocks items may still be waiting for.
+        if let Some(head) = self.head {
+            let oldest = head.saturating_sub(self.confirmations.max(64));
+            self.canonical = self.canonical.split_off(&oldest);
+        }
+
+        self.release()
+    }
+
+    /// Take the items whose block is confirmed, dropping those whose block isn't canonical.
+    fn release(&mut self) -> Vec<T> {
+        let Some(head) = self.head else {
+            return vec![];
+        };
+
+        let mut released = vec![];
+        let mut waiting = VecDeque::with_capacity(self.pending.len());
+
+        for item in self.pending.drain(..) {
+            let Some((number, hash)) = item.block() else {
+                continue;
+            };
+
+            if number + self.confirmations > head {
+                waiting.push_back(item);
+                continue;
+            }
+
+            self.keys.remove(&item.key());
+            match self.canonical.get(&number) {
+                Some(canonical) if *canonical != hash => debug!(number, %hash, "dropped item of orphaned block"),
+                _ => released.push(item),
+            }
+        }
+
+        self.pending = waiting;
+        released
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::VecDeque, sync::Mutex, time::Duration};
+
+    use alloy::{
+        consensus,
+        rpc::types::{Header, eth::Log},
+    };
+    use futures::StreamExt;
+
+    use super::{ConfirmationCollector, Confirmations, IConfirmable};
+    use crate::{CollectorStream, ICollector, collector::ChainEvent};
+
+    /// Emits one batch per subscription, then stays open if the batch says so and ends otherwise.
+    struct Batches<T> {
+        batches: Mutex<VecDeque<(Vec<T>, bool)>>,
+    }
+
+    impl<T> Batches<T> {
+        fn new(batches: impl IntoIterator<Item = (Vec<T>, bool)>) -> Box<Self> {
+            Box::new(Self {
+                batches: Mutex::new(batches.into_iter().collect()),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl<T: Send + Sync + 'static> ICollector<T> for Batches<T> {
+        async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, T>> {
+            let (batch, open) = self.batches.lock().unwrap().pop_front().expect("no more subscriptions");
+            let stream = async_stream::stream! {
+                for item in batch {
+                    yield item;
+                }
+                if open {
+                    futures::future::pending::<()>().await;
+                }
+            };
+
+            Ok(Box::pin(stream))
+        }
+    }
+
+    fn header(number: u64, parent: &Header, fork: u64) -> Header {
+        Header::new(consensus::Header {
+            number,
+            parent_hash: parent.hash,
+            timestamp: fork,
+            ..Default::default()
+        })
+    }
+
+    fn log(block: &Header, log_index: u64, removed: bool) -> Log {
+        Log {
+            block_number: Some(block.inner.number),
+            block_hash: Some(block.hash),
+            log_index: Some(log_index),
+            removed,
+            ..Default::default()
+        }
+    }
+
+    fn indexes(logs: &[Log]) -> Vec<u64> {
+        logs.iter().map(|log| log.log_index.unwrap()).collect()
+    }
+
+    #[test]
+    fn test_logs_are_released_once_confirmed() {
+        let genesis = Header::default();
+        let b1 = header(1, &genesis, 0);
+        let b2 = header(2, &b1, 0);
+        let b3 = header(3, &b2, 0);
+
+        let mut confirmations = Confirmations::new(2);
+        assert!(
+            confirmations
+                .on_chain_event(&ChainEvent::NewBlock(b1.clone()))
+                .is_empty()
+        );
+        assert!(confirmations.on_item(log(&b1, 0, false)).is_empty());
+        assert!(confirmations.on_item(log(&b1, 1, false)).is_empty());
+        // Emitted again by a resubscribed collector.
+        assert!(confirmations.on_item(log(&b1, 0, false)).is_empty());
+        assert_eq!(confirmations.pending.len(), 2);
+        assert!(
+            confirmations
+                .on_chain_event(&ChainEvent::NewBlock(b2.clone()))
+                .is_empty()
+        );
+
+        // The second log was removed before it was confirmed.
+        assert!(confirmations.on_item(log(&b1, 1, true)).is_empty());
+        let released = confirmations.on_chain_event(&ChainEvent::NewBlock(b3.clone()));
+        assert_eq!(indexes(&released), vec![0]);
+        assert!(confirmations.keys.is_empty());
+
+        // Logs of blocks deep enough already are let through right away.
+        assert_eq!(indexes(&confirmations.on_item(log(&b1, 2, false))), vec![2]);
+
+        let pending = Log::default();
+        assert!(pending.block().is_none());
+        assert_eq!(confirmations.on_item(pending).len(), 1);
+    }
+
+    #[test]
+    fn test_logs_of_orphaned_blocks_are_dropped() {
+        let genesis = Header::default();
+        let b1 = header(1, &genesis, 0);
+        let b2 = header(2, &b1, 0);
+        let b2_fork = header(2, &b1, 1);
+        let b3_fork = header(3, &b2_fork, 1);
+        assert_ne!(b2.hash, b2_fork.hash);
+
+        let mut confirmations = Confirmations::new(1);
+        confirmations.on_chain_event(&ChainEvent::NewBlock(b1.clone()));
+        confirmations.on_chain_event(&ChainEvent::NewBlock(b2.clone()));
+        confirmations.on_item(log(&b2, 0, false));
+
+        let reorg = ChainEvent::Reorg {
+            removed: vec![b2.clone()],
+            added: vec![b2_fork.clone(), b3_fork.clone()],
+        };
+        assert!(confirmations.on_chain_event(&reorg).is_empty());
+
+        // A log of the orphaned block arriving late is dropped once its height is confirmed.
+        assert!(confirmations.on_item(log(&b2, 1, false)).is_empty());
+        assert_eq!(indexes(&confirmations.on_item(log(&b2_fork, 2, false))), vec![2]);
+        assert!(confirmations.pending.is_empty());
+        assert!(confirmations.keys.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pending_logs_survive_resubscription() {
+        let genesis = Header::default();
+        let b1 = header(1, &genesis, 0);
+        let b2 = header(2, &b1, 0);
+        let b3 = header(3, &b2, 0);
+
+        let logs = Batches::new([(vec![log(&b1, 0, false)], false), (vec![], true)]);
+        let chain = Batches::new([
+            (vec![ChainEvent::NewBlock(b1.clone())], true),
+            (vec![ChainEvent::NewBlock(b2), ChainEvent::NewBlock(b3)], true),
+        ]);
+        let collector = ConfirmationCollector::new(logs, chain, 2);
+
+        // The log stream ends before the log is confirmed.
+        let mut stream = collector.get_event_stream().await.unwrap();
+        assert!(stream.next().await.is_none());
+        drop(stream);
+
+        // It is released once confirmed after resubscribing.
+        let mut stream = collector.get_event_stream().await.unwrap();
+        let released = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap();
+        assert_eq!(released.unwrap().log_index, Some(0));
+    }
+}
@@ -6,11 +6,19 @@ use async_trait::async_trait;
 use futures::StreamExt;
 use tracing::{error, warn};
 
+use super::{
+    backfill::{BlockGaps, DEFAULT_MAX_BACKFILL},
+    checkpoint::{Checkpoint, ICheckpointStore},
+};
 use crate::{CollectorStream, ICollector};
 
+/// Emits every new block with its transactions. Blocks skipped by the subscription, or missed while resubscribing,
+/// are fetched and emitted in order before the next live block.
 pub struct FullBlockCollector {
     provider: Arc<dyn Provider>,
     retry_interval: Duration,
+    gaps: BlockGaps,
+    checkpoint: Option<Checkpoint>,
 }
 
 impl FullBlockCollector {
@@ -19,11 +27,50 @@ impl FullBlockCollector {
     }
 
     /// Create a new `FullBlockCollector` with a custom retry interval. A retry will happen when the client returns
-    /// "header not found"
+    /// "header not found" or an error, until the block is fetched
     pub fn new_with_config(provider: Arc<dyn Provider>, retry_interval: Duration) -> Self {
         Self {
             provider,
             retry_interval,
+            gaps: BlockGaps::new(DEFAULT_MAX_BACKFILL),
+            checkpoint: None,
+        }
+    }
+
+    /// Backfill at most `max_backfill` missed blocks, the most recent ones, defaults to 128.
+    pub fn with_max_backfill(mut self, max_backfill: u64) -> Self {
+        self.gaps = BlockGaps::new(max_backfill);
+        self
+    }
+
+    /// Save the last emitted block to `store` under `key`. On start, all the blocks after the one saved by a previous
+    /// run are backfilled, regardless of `max_backfill`.
+    pub fn with_checkpoint(mut self, store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        self.checkpoint = Some(Checkpoint::new(store, key));
+        self
+    }
+
+    async fn fetch_block(&self, block_number: u64) -> Block {
+        let mut attempts = 0;
+
+        loop {
+            match self.provider.get_block_by_number(block_number.into()).full().await {
+                Ok(Some(block)) => return block,
+                Ok(None) => {
+                    if attempts % 5 == 0 {
+                        warn!("block not found yet: {}", block_number);
+                    }
+                }
+                Err(e) => {
+                    error!(
+                        "fail to get full block: {:#}, block number: {}, attempts: {}",
+                        e, block_number, attempts
+                    );
+                }
+            };
+
+            attempts += 1;
+            tokio::time::sleep(self.retry_interval).await;
         }
     }
 }
@@ -34,37 +81,30 @@ impl ICollector<Block> for FullBlockCollector {
         "Full Block Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Block>> {
-        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
+        if let Some(checkpoint) = &self.checkpoint
+            && let Some(block) = checkpoint.load().await
+        {
+            self.gaps.resume_from(block);
+        }
 
-        let mut attempts = 0;
+        let mut stream = self.provider.subscribe_blocks().await?.into_stream();
 
         let stream = async_stream::stream! {
-            while let Some(block) = stream.next().await {
-                let block_number = block.number;
-
-                loop {
-                    match self.provider.get_block_by_number(block_number.into()).full().await {
-                        Ok(Some(block)) => {
-                            yield block;
-                        }
-                        Ok(None) => {
-                            if attempts % 5 == 0 {
-                                warn!("block not found yet: {}", block_number);
-                            } else {
-                                error!("block not found yet: {}", block_number);
-                            }
-
-                            attempts += 1;
-                            tokio::time::sleep(self.retry_interval).await;
-                            continue;
-                        }
-                        Err(e) => {
-                            error!("fail to get full block: {:#}, block number: {}", e, block_number);
-                        }
-                    };
-
-                    break;
+            while let Some(header) = stream.next().await {
+                let missing = self.gaps.missing_before(header.number);
+
+                for block_number in missing.chain(std::iter::once(header.number)) {
+                    let block = self.fetch_block(block_number).await;
+                    self.gaps.emitted(block_number);
+                    yield block;
+                    if let Some(checkpoint) = &self.checkpoint {
+                        checkpoint.save(block_number).await;
+                    }
                 }
             }
         };
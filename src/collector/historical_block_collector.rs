@@ -0,0 +1,240 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy::{
+    providers::Provider,
+    rpc::types::{
+        Header,
+        eth::{Block, Filter, Log},
+    },
+};
+use async_trait::async_trait;
+use tracing::{error, warn};
+
+use crate::{CollectorStream, ICollector, SimulatedClock};
+
+/// The part shared by the historical collectors: a block range, the provider to walk it with and an optional
+/// clock to move along with block timestamps.
+struct BlockRange {
+    provider: Arc<dyn Provider>,
+    from: u64,
+    to: u64,
+    clock: Option<SimulatedClock>,
+    retry_interval: Duration,
+}
+
+impl BlockRange {
+    fn new(provider: Arc<dyn Provider>, from: u64, to: u64) -> Self {
+        Self {
+            provider,
+            from,
+            to,
+            clock: None,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+
+    /// Fetch a block, retrying on RPC errors. Returns `None` if the block doesn't exist.
+    async fn get_block(&self, number: u64, full: bool) -> Option<Block> {
+        loop {
+            let request = self.provider.get_block_by_number(number.into());
+            let request = if full { request.full() } else { request };
+
+            match request.await {
+                Ok(block) => return block,
+                Err(e) => {
+                    warn!("fail to get block {number}: {e:#}, retrying");
+                    tokio::time::sleep(self.retry_interval).await;
+                }
+            }
+        }
+    }
+
+    /// Move the clock to the block's timestamp and wait for the alarms that became due to be handled, so e.g. the
+    /// ticks due by then are emitted before the block.
+    async fn advance_clock(&self, header: &Header) {
+        if let Some(clock) = &self.clock {
+            clock.advance_to_unix_timestamp(header.timestamp);
+            clock.settled().await;
+        }
+    }
+}
+
+macro_rules! impl_block_range_builder {
+    ($collector: ident) => {
+        impl $collector {
+            /// Move `clock` to each block's timestamp right before the block is emitted.
+            pub fn with_clock(mut self, clock: SimulatedClock) -> Self {
+                self.range.clock = Some(clock);
+                self
+            }
+
+            /// How long to wait before retrying a failed RPC call. Defaults to one second.
+            pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+                self.range.retry_interval = retry_interval;
+                self
+            }
+        }
+    };
+}
+
+/// Emits the headers of the blocks in `from..=to`, in order, then finishes.
+pub struct HistoricalBlockCollector {
+    range: BlockRange,
+}
+
+impl HistoricalBlockCollector {
+    pub fn new(provider: Arc<dyn Provider>, from: u64, to: u64) -> Self {
+        Self {
+            range: BlockRange::new(provider, from, to),
+        }
+    }
+}
+
+impl_block_range_builder!(HistoricalBlockCollector);
+
+#[async_trait]
+impl ICollector<Header> for HistoricalBlockCollector {
+    fn name(&self) -> &str {
+        "Historical Block Collector"
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.range.provider.get_chain_id().await.ok()
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Header>> {
+        let range = &self.range;
+
+        let stream = async_stream::stream! {
+            for number in range.from..=range.to {
+                let Some(block) = range.get_block(number, false).await else {
+                    error!("block {number} not found, stopping");
+                    break;
+                };
+
+                range.advance_clock(&block.header).await;
+                yield block.header;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Emits the full blocks in `from..=to`, in order, then finishes.
+pub struct HistoricalFullBlockCollector {
+    range: BlockRange,
+}
+
+impl HistoricalFullBlockCollector {
+    pub fn new(provider: Arc<dyn Provider>, from: u64, to: u64) -> Self {
+        Self {
+            range: BlockRange::new(provider, from, to),
+        }
+    }
+}
+
+impl_block_range_builder!(HistoricalFullBlockCollector);
+
+#[async_trait]
+impl ICollector<Block> for HistoricalFullBlockCollector {
+    fn name(&self) -> &str {
+        "Historical Full Block Collector"
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.range.provider.get_chain_id().await.ok()
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Block>> {
+        let range = &self.range;
+
+        let stream = async_stream::stream! {
+            for number in range.from..=range.to {
+                let Some(block) = range.get_block(number, true).await else {
+                    error!("block {number} not found, stopping");
+                    break;
+                };
+
+                range.advance_clock(&block.header).await;
+                yield block;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Emits the header and the logs matching `filter` of each block in `from..=to`, in order, then finishes.
+pub struct HistoricalLogsInBlockCollector {
+    range: BlockRange,
+    filter: Filter,
+}
+
+impl HistoricalLogsInBlockCollector {
+    pub fn new(provider: Arc<dyn Provider>, filter: Filter, from: u64, to: u64) -> Self {
+        Self {
+            range: BlockRange::new(provider, from, to),
+            filter,
+        }
+    }
+
+    async fn get_logs(&self, header: &Header) -> Vec<Log> {
+        let filter = self.filter.clone().at_block_hash(header.hash);
+
+        loop {
+            match self.range.provider.get_logs(&filter).await {
+                Ok(logs) => return logs,
+                Err(e) => {
+                    warn!(block_hash = ?header.hash, "fail to get logs: {e:#}, retrying");
+                    tokio::time::sleep(self.range.retry_interval).await;
+                }
+            }
+        }
+    }
+}
+
+impl_block_range_builder!(HistoricalLogsInBlockCollector);
+
+#[async_trait]
+impl ICollector<(Header, Vec<Log>)> for HistoricalLogsInBlockCollector {
+    fn name(&self) -> &str {
+        "Historical Logs In Block Collector"
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.range.provider.get_chain_id().await.ok()
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, (Header, Vec<Log>)>> {
+        let range = &self.range;
+
+        let stream = async_stream::stream! {
+            for number in range.from..=range.to {
+                let Some(block) = range.get_block(number, false).await else {
+                    error!("block {number} not found, stopping");
+                    break;
+                };
+
+                let logs = self.get_logs(&block.header).await;
+
+                range.advance_clock(&block.header).await;
+                yield (block.header, logs);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
@@ -1,8 +1,11 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
 
 use async_trait::async_trait;
 
-use crate::{CollectorStream, ICollector};
+use crate::{Alarm, CollectorStream, ICollector, SimulatedClock};
 
 pub struct IntervalCollector {
     interval: Duration,
@@ -31,3 +34,99 @@ impl ICollector<Instant> for IntervalCollector {
         Ok(Box::pin(stream))
     }
 }
+
+/// Emits the time of a [`SimulatedClock`] every `interval` of simulated time, e.g. while backtesting.
+///
+/// The first tick is scheduled when the collector is built, so build it before the clock starts moving. When the
+/// clock jumps past several ticks at once, a single tick is emitted and the missed ones are skipped. Each tick is
+/// handed to the engine before the clock settles, so it comes before the events emitted once the clock has moved.
+pub struct SimulatedIntervalCollector {
+    interval: Duration,
+    clock: SimulatedClock,
+    first: Mutex<Option<Alarm>>,
+}
+
+impl SimulatedIntervalCollector {
+    pub fn new(interval: Duration, clock: SimulatedClock) -> Self {
+        let interval = interval.max(Duration::from_millis(1));
+        let first = clock.alarm(clock.now() + interval);
+
+        Self {
+            interval,
+            clock,
+            first: Mutex::new(Some(first)),
+        }
+    }
+}
+
+#[async_trait]
+impl ICollector<SystemTime> for SimulatedIntervalCollector {
+    fn name(&self) -> &str {
+        "Simulated Interval Collector"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, SystemTime>> {
+        // After a restart, the schedule starts over from the clock's time.
+        let first = self.first.lock().unwrap().take();
+        let mut alarm = first.unwrap_or_else(|| self.clock.alarm(self.clock.now() + self.interval));
+
+        let stream = async_stream::stream! {
+            loop {
+                let deadline = alarm.deadline();
+                let wakeup = alarm.wait().await;
+                let now = self.clock.now();
+
+                // Set the next alarm before letting the clock move on, so it can't be missed.
+                alarm = self.clock.alarm(next_tick(deadline, now, self.interval));
+                yield now;
+                drop(wakeup);
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// The first tick after `now`, on the schedule of `tick` every `interval`.
+fn next_tick(tick: SystemTime, now: SystemTime, interval: Duration) -> SystemTime {
+    let missed = now.duration_since(tick).unwrap_or_default().as_nanos() / interval.as_nanos();
+    let skipped = u32::try_from(missed).map_or(Duration::MAX, |missed| interval.saturating_mul(missed));
+
+    tick.checked_add(skipped)
+        .and_then(|tick| tick.checked_add(interval))
+        .unwrap_or(now + interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use futures::StreamExt;
+
+    use super::SimulatedIntervalCollector;
+    use crate::{ICollector, SimulatedClock};
+
+    #[tokio::test]
+    async fn test_simulated_clock_skips_missed_ticks() {
+        let clock = SimulatedClock::default();
+        let collector = SimulatedIntervalCollector::new(Duration::from_secs(2), clock.clone());
+
+        // Jump from the epoch to a real date before the stream starts: one tick, not one per missed interval.
+        let date = UNIX_EPOCH + Duration::from_secs(1_700_000_001);
+        clock.advance_to(date);
+
+        let mut stream = collector.get_event_stream().await.unwrap();
+        assert_eq!(stream.next().await, Some(date));
+
+        // The tick is held until the stream is polled again.
+        let settled = tokio::time::timeout(Duration::from_millis(50), clock.settled()).await;
+        assert!(settled.is_err(), "settled before the tick was handed on");
+
+        clock.advance_to(date + Duration::from_secs(1));
+        assert_eq!(stream.next().await, Some(date + Duration::from_secs(1)));
+
+        clock.advance_to(date + Duration::from_secs(2));
+        let next = tokio::time::timeout(Duration::from_millis(50), stream.next()).await;
+        assert!(next.is_err(), "no tick before the next deadline");
+    }
+}
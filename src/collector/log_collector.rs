@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use alloy::{
     providers::Provider,
@@ -6,17 +9,73 @@ use alloy::{
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use tracing::{debug, error, warn};
 
+use super::checkpoint::{Checkpoint, ICheckpointStore};
 use crate::{CollectorStream, ICollector};
 
+/// How many times the logs of a single block are fetched again after being rejected as too large, before giving up.
+pub const MAX_BLOCK_RETRIES: u32 = 3;
+
+/// Emits the logs matching `filter` as they are included in new blocks.
+///
+/// With [`with_from_block`](Self::with_from_block), the logs of past blocks are fetched first with `eth_getLogs`, in
+/// chunks that shrink when the provider rejects a range as too large and grow back as requests succeed. The
+/// collector then subscribes to new logs, fetches those of the blocks produced in the meantime, and skips live logs
+/// of blocks it has already paged through. A new event stream picks up after the last log emitted by the previous
+/// one. If the provider keeps rejecting the logs of a single block as too large, the stream ends after
+/// [`MAX_BLOCK_RETRIES`] retries so the engine restarts it according to its restart policy.
+///
+/// With [`with_checkpoint`](Self::with_checkpoint), the last block whose logs were all emitted is saved as the
+/// collector goes, and a restart pages through history from the block after it. Logs of the block in progress at the
+/// time of a crash may be emitted twice.
 pub struct LogCollector {
     provider: Arc<dyn Provider>,
     filter: Filter,
+    from_block: Option<u64>,
+    max_chunk_size: u64,
+    retry_interval: Duration,
+    checkpoint: Option<Checkpoint>,
+    /// How far the logs have been emitted, once paging through history started.
+    cursor: Mutex<Option<Cursor>>,
 }
 
 impl LogCollector {
     pub fn new(provider: Arc<dyn Provider>, filter: Filter) -> Self {
-        Self { provider, filter }
+        Self {
+            provider,
+            filter,
+            from_block: None,
+            max_chunk_size: 2000,
+            retry_interval: Duration::from_secs(1),
+            checkpoint: None,
+            cursor: Mutex::new(None),
+        }
+    }
+
+    /// Emit the logs from `from_block` on before the live ones.
+    pub fn with_from_block(mut self, from_block: u64) -> Self {
+        self.from_block = Some(from_block);
+        self
+    }
+
+    /// The largest number of blocks to fetch logs of in one request. Defaults to 2000.
+    pub fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.max_chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// How long to wait before retrying a failed RPC call. Defaults to one second.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Save the last block whose logs were all emitted to `store` under `key`, and resume from the block after it
+    /// on start, in place of `from_block`.
+    pub fn with_checkpoint(mut self, store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        self.checkpoint = Some(Checkpoint::new(store, key));
+        self
     }
 }
 
@@ -26,9 +85,344 @@ impl ICollector<Log> for LogCollector {
         "Log Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Log>> {
-        let stream = self.provider.subscribe_logs(&self.filter).await?;
-        let stream = stream.into_stream().filter_map(|v| async move { Some(v) });
+        let cursor = *self.cursor.lock().unwrap();
+        let mut cursor = match cursor {
+            // Resubscribing, pick up after the last log emitted.
+            Some(cursor) => cursor,
+            None => {
+                let from_block = match &self.checkpoint {
+                    Some(checkpoint) => match checkpoint.load().await {
+                        Some(block) => Some(block + 1),
+                        // Nothing saved yet, start from `from_block` or the head.
+                        None => match self.from_block {
+                            Some(from_block) => Some(from_block),
+                            None => Some(self.provider.get_block_number().await? + 1),
+                        },
+                    },
+                    None => self.from_block,
+                };
+
+                let Some(from_block) = from_block else {
+                    let stream = self.provider.subscribe_logs(&self.filter).await?;
+                    let stream = stream.into_stream().filter_map(|v| async move { Some(v) });
+                    return Ok(Box::pin(stream));
+                };
+
+                Cursor::new(from_block)
+            }
+        };
+
+        let stream = async_stream::stream! {
+            let mut chunk = ChunkSize::new(self.max_chunk_size);
+            let mut live = None;
+            let mut block_retries = 0;
+
+            // Page through history up to the head, then subscribe and page through the blocks produced meanwhile.
+            // Subscribing only once caught up keeps the subscription from buffering, and lagging, the whole history.
+            loop {
+                let head = match self.provider.get_block_number().await {
+                    Ok(head) => head,
+                    Err(e) => {
+                        warn!("fail to get block number: {e:#}, retrying");
+                        tokio::time::sleep(self.retry_interval).await;
+                        continue;
+                    }
+                };
+
+                while cursor.next <= head {
+                    let from = cursor.next;
+                    let to = chunk.end(from, head);
+                    let filter = self.filter.clone().from_block(from).to_block(to);
+
+                    match self.provider.get_logs(&filter).await {
+                        Ok(logs) => {
+                            for log in logs {
+                                if cursor.is_emitted(&log) {
+                                    continue;
+                                }
+                                cursor.emitted(&log);
+                                *self.cursor.lock().unwrap() = Some(cursor);
+                                yield log;
+                            }
+                            cursor = Cursor::new(to + 1);
+                            *self.cursor.lock().unwrap() = Some(cursor);
+                            chunk.grow();
+                            block_retries = 0;
+                            if let Some(checkpoint) = &self.checkpoint {
+                                checkpoint.save(to).await;
+                            }
+                        }
+                        Err(e) if is_range_error(&e.to_string()) => {
+                            if chunk.shrink() {
+                                debug!(from, to, chunk_size = chunk.size, "log range too large, shrinking");
+                            } else if block_retries < MAX_BLOCK_RETRIES {
+                                // A single block can't be split, the provider may be struggling, try it again.
+                                block_retries += 1;
+                                warn!("fail to get logs of block {from}: {e:#}, retrying");
+                                tokio::time::sleep(self.retry_interval).await;
+                            } else {
+                                error!("fail to get logs of block {from} after {block_retries} retries: {e:#}");
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            warn!("fail to get logs of blocks {from}..={to}: {e:#}, retrying");
+                            tokio::time::sleep(self.retry_interval).await;
+                        }
+                    }
+                }
+
+                if live.is_some() {
+                    break;
+                }
+
+                match self.provider.subscribe_logs(&self.filter).await {
+                    Ok(subscription) => live = Some(subscription.into_stream()),
+                    Err(e) => {
+                        error!("fail to subscribe to logs: {e:#}");
+                        return;
+                    }
+                }
+            }
+
+            let Some(mut live) = live else {
+                return;
+            };
+
+            while let Some(log) = live.next().await {
+                // Removals still go through, they may retract a log emitted from history.
+                if !log.removed {
+                    if cursor.is_emitted(&log) {
+                        continue;
+                    }
+
+                    if let Some(done) = cursor.emitted(&log)
+                        && let Some(checkpoint) = &self.checkpoint
+                    {
+                        checkpoint.save(done).await;
+                    }
+                    *self.cursor.lock().unwrap() = Some(cursor);
+                }
+                yield log;
+            }
+        };
+
         Ok(Box::pin(stream))
     }
 }
+
+/// Whether an `eth_getLogs` error asks for a smaller block range, as opposed to a transient failure.
+fn is_range_error(message: &str) -> bool {
+    const PATTERNS: [&str; 7] = [
+        "query returned more than",
+        "range is too large",
+        "range too large",
+        "range is too wide",
+        "maximum block range",
+        "too many logs",
+        "response size",
+    ];
+
+    let message = message.to_lowercase();
+    PATTERNS.iter().any(|pattern| message.contains(pattern))
+}
+
+/// The position after the last log emitted.
+#[derive(Debug, Clone, Copy)]
+struct Cursor {
+    /// The first block whose logs haven't all been emitted.
+    next: u64,
+    /// The index of the last log emitted from `next`.
+    last_index: Option<u64>,
+}
+
+impl Cursor {
+    fn new(next: u64) -> Self {
+        Self { next, last_index: None }
+    }
+
+    fn is_emitted(&self, log: &Log) -> bool {
+        match log.block_number {
+            Some(number) if number == self.next => log
+                .log_index
+                .zip(self.last_index)
+                .is_some_and(|(index, last)| index <= last),
+            Some(number) => number < self.next,
+            None => false,
+        }
+    }
+
+    /// Move past `log`, returns the last block whose logs were all emitted if that changed.
+    fn emitted(&mut self, log: &Log) -> Option<u64> {
+        let number = log.block_number?;
+        let done = (number > self.next).then(|| number - 1);
+
+        if number >= self.next {
+            self.next = number;
+            self.last_index = log.log_index;
+        }
+
+        done
+    }
+}
+
+/// The number of blocks to fetch logs of in the next `eth_getLogs` request.
+struct ChunkSize {
+    size: u64,
+    max: u64,
+}
+
+impl ChunkSize {
+    fn new(max: u64) -> Self {
+        Self { size: max, max }
+    }
+
+    /// The last block of the chunk starting at `from`, not past `head`.
+    fn end(&self, from: u64, head: u64) -> u64 {
+        from.saturating_add(self.size - 1).min(head)
+    }
+
+    /// Halve the chunk, returns false if it can't get any smaller.
+    fn shrink(&mut self) -> bool {
+        if self.size == 1 {
+            return false;
+        }
+        self.size /= 2;
+        true
+    }
+
+    fn grow(&mut self) {
+        self.size = self.size.saturating_mul(2).min(self.max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use alloy::{
+        providers::{Provider, ProviderBuilder},
+        rpc::types::eth::{Filter, Log},
+        transports::mock::Asserter,
+    };
+    use futures::StreamExt;
+
+    use super::{ChunkSize, LogCollector, MAX_BLOCK_RETRIES, is_range_error};
+    use crate::ICollector;
+
+    fn log(block_number: u64, log_index: u64) -> Log {
+        Log {
+            block_number: Some(block_number),
+            log_index: Some(log_index),
+            ..Default::default()
+        }
+    }
+
+    fn positions(logs: Vec<Log>) -> Vec<(u64, u64)> {
+        logs.iter()
+            .map(|log| (log.block_number.unwrap(), log.log_index.unwrap()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_resubscribe_resumes_after_emitted_logs() {
+        let asserter = Asserter::new();
+        let provider: Arc<dyn Provider> = Arc::new(ProviderBuilder::new().connect_mocked_client(asserter.clone()));
+        let collector = LogCollector::new(provider, Filter::new())
+            .with_from_block(1)
+            .with_chunk_size(1)
+            .with_retry_interval(Duration::from_millis(1));
+
+        // The mocked provider can't subscribe, so each stream ends once caught up with the head.
+        asserter.push_success(&2u64);
+        asserter.push_success(&vec![log(1, 0)]);
+        asserter.push_success(&vec![log(2, 1), log(2, 2)]);
+        let logs = collector.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(positions(logs), vec![(1, 0), (2, 1), (2, 2)]);
+
+        // Resubscribing only fetches the new blocks, and a range error at the smallest chunk is retried.
+        asserter.push_success(&3u64);
+        asserter.push_failure_msg("query returned more than 10000 results");
+        asserter.push_success(&vec![log(3, 3)]);
+        let logs = tokio::time::timeout(
+            Duration::from_secs(1),
+            collector.get_event_stream().await.unwrap().collect(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(positions(logs), vec![(3, 3)]);
+        assert!(asserter.read_q().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_ends_when_a_block_is_always_too_large() {
+        let asserter = Asserter::new();
+        let provider: Arc<dyn Provider> = Arc::new(ProviderBuilder::new().connect_mocked_client(asserter.clone()));
+        let collector = LogCollector::new(provider, Filter::new())
+            .with_from_block(1)
+            .with_chunk_size(1)
+            .with_retry_interval(Duration::from_millis(1));
+
+        asserter.push_success(&1u64);
+        for _ in 0..=MAX_BLOCK_RETRIES {
+            asserter.push_failure_msg("query returned more than 10000 results");
+        }
+        let logs: Vec<Log> = tokio::time::timeout(
+            Duration::from_secs(1),
+            collector.get_event_stream().await.unwrap().collect(),
+        )
+        .await
+        .unwrap();
+        assert!(logs.is_empty());
+        assert!(asserter.read_q().is_empty());
+
+        // Restarting tries the block again.
+        asserter.push_success(&1u64);
+        asserter.push_success(&vec![log(1, 0)]);
+        let logs = collector.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(positions(logs), vec![(1, 0)]);
+    }
+
+    #[test]
+    fn test_chunk_size_adapts() {
+        let mut chunk = ChunkSize::new(1000);
+        assert_eq!(chunk.end(100, 10_000), 1099);
+        assert_eq!(chunk.end(100, 500), 500);
+
+        assert!(chunk.shrink());
+        assert!(chunk.shrink());
+        assert_eq!(chunk.end(100, 10_000), 349);
+
+        chunk.grow();
+        assert_eq!(chunk.size, 500);
+        chunk.grow();
+        chunk.grow();
+        assert_eq!(chunk.size, 1000);
+
+        let mut chunk = ChunkSize::new(3);
+        assert!(chunk.shrink());
+        assert!(!chunk.shrink());
+        assert_eq!(chunk.end(7, 100), 7);
+    }
+
+    #[test]
+    fn test_range_errors() {
+        assert!(is_range_error(
+            "server returned an error response: error code -32005: query returned more than 10000 results"
+        ));
+        assert!(is_range_error(
+            "server returned an error response: error code -32600: eth_getLogs block range is too large"
+        ));
+        assert!(is_range_error("Log response size exceeded"));
+        assert!(is_range_error("exceed maximum block range: 5000"));
+        assert!(!is_range_error("invalid block range"));
+        assert!(!is_range_error("block range out of bounds (from > to)"));
+        assert!(!is_range_error("connection reset by peer"));
+        assert!(!is_range_error("429 Too Many Requests: rate limit exceeded"));
+    }
+}
@@ -10,17 +10,59 @@ use alloy::{
 };
 use async_trait::async_trait;
 use futures::StreamExt;
+use tracing::{error, warn};
 
+use super::{
+    backfill::{BlockGaps, DEFAULT_MAX_BACKFILL},
+    checkpoint::{Checkpoint, ICheckpointStore},
+};
 use crate::{CollectorStream, ICollector};
 
+/// Emits every new block's header with its logs matching `filter`. Blocks skipped by the subscription, or missed
+/// while resubscribing, are fetched and emitted in order before the next live block; if one of them can't be
+/// fetched, the live block is held back and the gap is fetched again when the next one arrives.
 pub struct LogsInBlockCollector {
     provider: Arc<dyn Provider>,
     filter: Filter,
+    gaps: BlockGaps,
+    checkpoint: Option<Checkpoint>,
 }
 
 impl LogsInBlockCollector {
     pub fn new(provider: Arc<dyn Provider>, filter: Filter) -> Self {
-        Self { provider, filter }
+        Self {
+            provider,
+            filter,
+            gaps: BlockGaps::new(DEFAULT_MAX_BACKFILL),
+            checkpoint: None,
+        }
+    }
+
+    /// Backfill at most `max_backfill` missed blocks, the most recent ones, defaults to 128.
+    pub fn with_max_backfill(mut self, max_backfill: u64) -> Self {
+        self.gaps = BlockGaps::new(max_backfill);
+        self
+    }
+
+    /// Save the last emitted block to `store` under `key`. On start, all the blocks after the one saved by a previous
+    /// run are backfilled, regardless of `max_backfill`.
+    pub fn with_checkpoint(mut self, store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        self.checkpoint = Some(Checkpoint::new(store, key));
+        self
+    }
+
+    async fn missed_block(&self, number: u64) -> Option<Header> {
+        match self.provider.get_block_by_number(number.into()).await {
+            Ok(Some(block)) => Some(block.header),
+            Ok(None) => {
+                warn!("missed block not found: {}", number);
+                None
+            }
+            Err(e) => {
+                error!("fail to backfill block: {:#}, block number: {}", e, number);
+                None
+            }
+        }
     }
 
     async fn block_to_logs(&self, block_hash: BlockHash) -> Option<Vec<Log>> {
@@ -32,7 +74,7 @@ impl LogsInBlockCollector {
         match logs {
             Ok(logs) => Some(logs),
             Err(e) => {
-                tracing::error!(?block_hash, "fail to get logs: {e:#}");
+                error!(?block_hash, "fail to get logs: {e:#}");
                 None
             }
         }
@@ -45,17 +87,55 @@ impl ICollector<(Header, Vec<Log>)> for LogsInBlockCollector {
         "Logs In Block Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, (Header, Vec<Log>)>> {
+        if let Some(checkpoint) = &self.checkpoint
+            && let Some(block) = checkpoint.load().await
+        {
+            self.gaps.resume_from(block);
+        }
+
         let mut stream = self.provider.subscribe_blocks().await?.into_stream();
 
         let stream = async_stream::stream! {
             while let Some(block) = stream.next().await {
+                // On failure, the blocks from the missing one on are fetched again before the next live block.
+                let mut backfilled = true;
+                for number in self.gaps.missing_before(block.number) {
+                    let Some(missed) = self.missed_block(number).await else {
+                        backfilled = false;
+                        break;
+                    };
+                    let Some(logs) = self.block_to_logs(missed.hash).await else {
+                        backfilled = false;
+                        break;
+                    };
+
+                    self.gaps.emitted(number);
+                    yield (missed, logs);
+                    if let Some(checkpoint) = &self.checkpoint {
+                        checkpoint.save(number).await;
+                    }
+                }
+
+                if !backfilled {
+                    continue;
+                }
+
                 let logs = match self.block_to_logs(block.hash).await {
                     Some(logs) => logs,
                     None => continue,
                 };
 
+                let number = block.number;
+                self.gaps.emitted(number);
                 yield (block, logs);
+                if let Some(checkpoint) = &self.checkpoint {
+                    checkpoint.save(number).await;
+                }
             }
         };
 
@@ -36,6 +36,10 @@ impl ICollector<Transaction> for MempoolCollector {
         "Mempool Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Transaction>> {
         let stream = self
             .provider
@@ -1,8 +1,14 @@
 #[cfg(feature = "evm")]
+mod backfill;
+#[cfg(feature = "evm")]
 pub mod block_collector;
 #[cfg(feature = "evm")]
+pub mod confirmation_collector;
+#[cfg(feature = "evm")]
 pub mod full_block_collector;
 #[cfg(feature = "evm")]
+pub mod historical_block_collector;
+#[cfg(feature = "evm")]
 pub mod log_collector;
 #[cfg(feature = "evm")]
 pub mod logs_in_block_collector;
@@ -10,12 +16,20 @@ pub mod logs_in_block_collector;
 pub mod mempool_collector;
 #[cfg(feature = "evm")]
 pub mod poll_full_block_collector;
+#[cfg(feature = "evm")]
+pub mod reorg_collector;
 
 #[cfg(feature = "evm")]
 pub use block_collector::BlockCollector;
 #[cfg(feature = "evm")]
+pub use confirmation_collector::{ConfirmationCollector, IConfirmable};
+#[cfg(feature = "evm")]
 pub use full_block_collector::FullBlockCollector;
 #[cfg(feature = "evm")]
+pub use historical_block_collector::{
+    HistoricalBlockCollector, HistoricalFullBlockCollector, HistoricalLogsInBlockCollector,
+};
+#[cfg(feature = "evm")]
 pub use log_collector::LogCollector;
 #[cfg(feature = "evm")]
 pub use logs_in_block_collector::LogsInBlockCollector;
@@ -23,6 +37,21 @@ pub use logs_in_block_collector::LogsInBlockCollector;
 pub use mempool_collector::MempoolCollector;
 #[cfg(feature = "evm")]
 pub use poll_full_block_collector::PollFullBlockCollector;
+#[cfg(feature = "evm")]
+pub use reorg_collector::{ChainEvent, IBlock, ReorgCollector};
 
+pub mod checkpoint;
 pub mod interval_collector;
-pub use interval_collector::IntervalCollector;
+
+pub use checkpoint::{FileCheckpointStore, ICheckpointStore};
+pub use interval_collector::{IntervalCollector, SimulatedIntervalCollector};
+
+#[cfg(feature = "record")]
+pub mod recorder;
+#[cfg(feature = "record")]
+pub mod replay_collector;
+
+#[cfg(feature = "record")]
+pub use recorder::{RecordedEvent, RecordingCollector};
+#[cfg(feature = "record")]
+pub use replay_collector::{ReplayCollector, ReplaySpeed};
@@ -1,24 +1,36 @@
 use std::{
     sync::{
-        Arc,
-        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
     },
     time::Duration,
 };
 
 use alloy::{
+    primitives::BlockHash,
     providers::Provider,
     rpc::types::eth::{Block, BlockId},
 };
 use async_trait::async_trait;
-use tracing::error;
+use tracing::{error, warn};
 
+use super::{
+    backfill::{DEFAULT_MAX_BACKFILL, backfill_range},
+    checkpoint::{Checkpoint, ICheckpointStore},
+};
 use crate::{CollectorStream, ICollector};
 
+/// Polls the latest block every `interval`. Blocks produced between two polls are fetched and emitted in order
+/// before the latest one; if one of them can't be fetched, the latest block is held back until the next poll.
 pub struct PollFullBlockCollector {
     provider: Arc<dyn Provider>,
     interval: Duration,
-    current_block: AtomicU64,
+    max_backfill: u64,
+    /// Number and hash of the last block emitted.
+    current_block: Mutex<Option<(u64, BlockHash)>>,
+    checkpoint: Option<Checkpoint>,
+    /// Whether the collector is still catching up with a checkpoint, whose gap is backfilled whatever its size.
+    resumed: AtomicBool,
 }
 
 impl PollFullBlockCollector {
@@ -26,7 +38,46 @@ impl PollFullBlockCollector {
         Self {
             provider,
             interval,
-            current_block: AtomicU64::new(0),
+            max_backfill: DEFAULT_MAX_BACKFILL,
+            current_block: Mutex::new(None),
+            checkpoint: None,
+            resumed: AtomicBool::new(false),
+        }
+    }
+
+    /// Backfill at most `max_backfill` missed blocks, the most recent ones, defaults to 128.
+    pub fn with_max_backfill(mut self, max_backfill: u64) -> Self {
+        self.max_backfill = max_backfill;
+        self
+    }
+
+    /// Save the last emitted block to `store` under `key`. On start, all the blocks after the one saved by a previous
+    /// run are backfilled, regardless of `max_backfill`.
+    pub fn with_checkpoint(mut self, store: Arc<dyn ICheckpointStore>, key: impl Into<String>) -> Self {
+        self.checkpoint = Some(Checkpoint::new(store, key));
+        self
+    }
+
+    /// Pick up after the block saved by a previous run, unless blocks were emitted since.
+    async fn resume(&self, checkpoint: &Checkpoint) {
+        if self.current_block.lock().unwrap().is_some() {
+            return;
+        }
+
+        let Some(number) = checkpoint.load().await else {
+            return;
+        };
+
+        match self.provider.get_block_by_number(number.into()).await {
+            Ok(Some(block)) => {
+                let mut current_block = self.current_block.lock().unwrap();
+                if current_block.is_none() {
+                    *current_block = Some((number, block.header.hash));
+                    self.resumed.store(true, Ordering::Relaxed);
+                }
+            }
+            Ok(None) => warn!("checkpoint block not found: {}", number),
+            Err(e) => error!("fail to get checkpoint block: {:#}, block number: {}", e, number),
         }
     }
 }
@@ -37,19 +88,76 @@ impl ICollector<Block> for PollFullBlockCollector {
         "Poll Full Block Collector"
     }
 
+    async fn chain_id(&self) -> Option<u64> {
+        self.provider.get_chain_id().await.ok()
+    }
+
     async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, Block>> {
+        if let Some(checkpoint) = &self.checkpoint {
+            self.resume(checkpoint).await;
+        }
+
         let stream = async_stream::stream! {
             loop {
                 match self.provider.get_block(BlockId::latest()).full().await {
                     Ok(Some(block)) => {
-                        let current_block = block.header.number;
+                        let latest = (block.header.number, block.header.hash);
+
+                        // A newer block, or one replacing the current block at the same height after a reorg, along
+                        // with the blocks produced since the last poll.
+                        let missing = {
+                            let current_block = *self.current_block.lock().unwrap();
+                            let is_new = current_block.is_none_or(|(number, hash)| {
+                                latest.0 > number || (latest.0 == number && latest.1 != hash)
+                            });
+                            let last = current_block.map(|(number, _)| number);
+                            let max_backfill = if self.resumed.load(Ordering::Relaxed) {
+                                u64::MAX
+                            } else {
+                                self.max_backfill
+                            };
+                            is_new.then(|| backfill_range(last, latest.0, max_backfill))
+                        };
+
+                        let Some(missing) = missing else {
+                            tokio::time::sleep(self.interval).await;
+                            continue;
+                        };
+
+                        // On failure, the blocks from the missing one on are fetched again on the next poll.
+                        let mut backfilled = true;
+                        for number in missing {
+                            match self.provider.get_block_by_number(number.into()).full().await {
+                                Ok(Some(missed)) => {
+                                    *self.current_block.lock().unwrap() = Some((number, missed.header.hash));
+                                    yield missed;
+                                    if let Some(checkpoint) = &self.checkpoint {
+                                        checkpoint.save(number).await;
+                                    }
+                                }
+                                Ok(None) => {
+                                    warn!("missed block not found: {}", number);
+                                    backfilled = false;
+                                    break;
+                                }
+                                Err(e) => {
+                                    error!("fail to backfill block: {:#}, block number: {}", e, number);
+                                    backfilled = false;
+                                    break;
+                                }
+                            }
+                        }
 
-                        let old_block = self
-                            .current_block
-                            .fetch_max(current_block, Ordering::Relaxed);
+                        if !backfilled {
+                            tokio::time::sleep(self.interval).await;
+                            continue;
+                        }
+                        self.resumed.store(false, Ordering::Relaxed);
 
-                        if old_block < current_block {
-                            yield block;
+                        *self.current_block.lock().unwrap() = Some(latest);
+                        yield block;
+                        if let Some(checkpoint) = &self.checkpoint {
+                            checkpoint.save(latest.0).await;
                         }
                     }
                     Ok(None) => {
@@ -67,3 +175,43 @@ impl ICollector<Block> for PollFullBlockCollector {
         Ok(Box::pin(stream))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use alloy::{
+        providers::{Provider, ProviderBuilder},
+        rpc::types::eth::Block,
+        transports::mock::Asserter,
+    };
+    use futures::StreamExt;
+
+    use super::PollFullBlockCollector;
+    use crate::ICollector;
+
+    fn block(number: u64) -> Block {
+        let mut block: Block = Block::default();
+        block.header.inner.number = number;
+        block.header.hash = [number as u8; 32].into();
+        block
+    }
+
+    #[tokio::test]
+    async fn test_failed_backfill_is_retried() {
+        let asserter = Asserter::new();
+        let provider: Arc<dyn Provider> = Arc::new(ProviderBuilder::new().connect_mocked_client(asserter.clone()));
+        let collector = PollFullBlockCollector::new(provider, Duration::from_millis(1));
+
+        // Block 6 fails on the first attempt, so block 7 waits for the next poll.
+        asserter.push_success(&block(5));
+        asserter.push_success(&block(7));
+        asserter.push_failure_msg("connection reset");
+        asserter.push_success(&block(7));
+        asserter.push_success(&block(6));
+
+        let stream = collector.get_event_stream().await.unwrap();
+        let numbers: Vec<u64> = stream.take(3).map(|block| block.header.inner.number).collect().await;
+        assert_eq!(numbers, vec![5, 6, 7]);
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use eyre::WrapErr;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tracing::error;
+
+use crate::{CollectorStream, ICollector};
+
+/// One line of a recording: an event and the wall clock time it was emitted at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(serialize = "E: Serialize", deserialize = "E: DeserializeOwned"))]
+pub struct RecordedEvent<E> {
+    /// Microseconds since the unix epoch.
+    pub timestamp_us: u64,
+    pub event: E,
+}
+
+impl<E> RecordedEvent<E> {
+    pub fn now(event: E) -> Self {
+        let timestamp_us = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or_default();
+
+        Self { timestamp_us, event }
+    }
+}
+
+/// Wraps a collector and appends every event it emits to a JSONL file, to be played back later with
+/// [`ReplayCollector`](super::ReplayCollector). The file is written on a blocking thread, flushed whenever it has
+/// caught up with the events; the stream of a finite collector ends once its recording is complete.
+///
+/// # Examples
+///
+/// ```ignore
+/// let collector = RecordingCollector::new(Box::new(BlockCollector::new(provider)), "blocks.jsonl");
+/// engine.add_collector(map_collector!(collector, Event::Block));
+/// ```
+pub struct RecordingCollector<E> {
+    inner: Box<dyn ICollector<E>>,
+    path: PathBuf,
+}
+
+impl<E> RecordingCollector<E> {
+    pub fn new(collector: Box<dyn ICollector<E>>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: collector,
+            path: path.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for RecordingCollector<E>
+where
+    E: Serialize + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        let path = self.path.clone();
+        let file = tokio::task::spawn_blocking(move || OpenOptions::new().create(true).append(true).open(path))
+            .await?
+            .wrap_err_with(|| format!("fail to open recording file {}", self.path.display()))?;
+
+        let (lines, receiver) = mpsc::unbounded_channel();
+        let writer = tokio::task::spawn_blocking(move || write_lines(BufWriter::new(file), receiver));
+
+        let mut events = self.inner.get_event_stream().await?;
+        let stream = async_stream::stream! {
+            while let Some(event) = events.next().await {
+                let record = RecordedEvent::now(event);
+
+                match serde_json::to_vec(&record) {
+                    Ok(line) => {
+                        let _ = lines.send(line);
+                    }
+                    Err(e) => error!("fail to record event: {e:#}"),
+                }
+
+                yield record.event;
+            }
+
+            drop(lines);
+            if let Err(e) = writer.await {
+                error!("recording task terminated unexpectedly: {e:#}");
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Append each line to the recording, flushing once there is nothing left to write. Returns when the stream is
+/// dropped.
+fn write_lines(mut writer: BufWriter<File>, mut lines: UnboundedReceiver<Vec<u8>>) {
+    while let Some(line) = lines.blocking_recv() {
+        let result = writer
+            .write_all(&line)
+            .and_then(|_| writer.write_all(b"\n"))
+            .and_then(|_| if lines.is_empty() { writer.flush() } else { Ok(()) });
+
+        if let Err(e) = result {
+            error!("fail to record event: {e:#}");
+        }
+    }
+
+    if let Err(e) = writer.flush() {
+        error!("fail to flush recording: {e:#}");
+    }
+}
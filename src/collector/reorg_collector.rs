@@ -0,0 +1,457 @@
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use alloy::{
+    primitives::B256,
+    providers::Provider,
+    rpc::types::{Header, eth::Block},
+};
+use async_trait::async_trait;
+use eyre::{OptionExt, Result};
+use futures::StreamExt;
+use tracing::{error, warn};
+
+use crate::{CollectorStream, ICollector};
+
+/// A block or header, as far as following the canonical chain is concerned.
+#[async_trait]
+pub trait IBlock: Clone + Send + Sync + 'static {
+    fn number(&self) -> u64;
+
+    fn hash(&self) -> B256;
+
+    fn parent_hash(&self) -> B256;
+
+    async fn fetch_by_hash(provider: &dyn Provider, hash: B256) -> Result<Option<Self>>;
+}
+
+#[async_trait]
+impl IBlock for Header {
+    fn number(&self) -> u64 {
+        self.inner.number
+    }
+
+    fn hash(&self) -> B256 {
+        self.hash
+    }
+
+    fn parent_hash(&self) -> B256 {
+        self.inner.parent_hash
+    }
+
+    async fn fetch_by_hash(provider: &dyn Provider, hash: B256) -> Result<Option<Self>> {
+        Ok(provider.get_block_by_hash(hash).await?.map(|block| block.header))
+    }
+}
+
+#[async_trait]
+impl IBlock for Block {
+    fn number(&self) -> u64 {
+        self.header.inner.number
+    }
+
+    fn hash(&self) -> B256 {
+        self.header.hash
+    }
+
+    fn parent_hash(&self) -> B256 {
+        self.header.inner.parent_hash
+    }
+
+    async fn fetch_by_hash(provider: &dyn Provider, hash: B256) -> Result<Option<Self>> {
+        Ok(provider.get_block_by_hash(hash).full().await?)
+    }
+}
+
+/// A change of the canonical chain, emitted by [`ReorgCollector`].
+#[derive(Debug, Clone)]
+pub enum ChainEvent<B> {
+    /// A block extending the chain.
+    NewBlock(B),
+    /// Blocks that are no longer canonical, oldest first, and the blocks that replaced them, oldest first and ending
+    /// with the new tip.
+    Reorg { removed: Vec<B>, added: Vec<B> },
+}
+
+/// Follows the canonical chain behind the blocks of another collector, such as a [`BlockCollector`] or a
+/// [`PollFullBlockCollector`], keeping the hashes of the last `depth` blocks.
+///
+/// A block whose parent isn't the tip is resolved by fetching its ancestors until one of them is known: blocks
+/// skipped by the subscription are emitted as [`NewBlock`](ChainEvent::NewBlock)s, and blocks orphaned by a reorg
+/// as a [`Reorg`](ChainEvent::Reorg) along with their replacements. A reorg deeper than `depth` removes every known
+/// block. After a gap longer than `depth`, e.g. a long outage, whether the known blocks are still canonical can't be
+/// told: the window starts over from the fetched blocks, which are emitted as `NewBlock`s. Blocks seen before are
+/// dropped. The window survives resubscriptions, so a reorg while the subscription was down is still detected.
+///
+/// ```ignore
+/// let blocks = ReorgCollector::new(Box::new(BlockCollector::new(provider.clone())), provider);
+/// engine.add_collector(map_collector!(blocks, Event::Chain));
+/// ```
+///
+/// [`BlockCollector`]: super::BlockCollector
+/// [`PollFullBlockCollector`]: super::PollFullBlockCollector
+pub struct ReorgCollector<B> {
+    inner: Box<dyn ICollector<B>>,
+    provider: Arc<dyn Provider>,
+    tracker: Mutex<ChainTracker<B>>,
+}
+
+impl<B: IBlock> ReorgCollector<B> {
+    pub fn new(collector: Box<dyn ICollector<B>>, provider: Arc<dyn Provider>) -> Self {
+        Self {
+            inner: collector,
+            provider,
+            tracker: Mutex::new(ChainTracker::new(64)),
+        }
+    }
+
+    /// How many recent blocks to keep, and so the deepest reorg that can be resolved.
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.tracker = Mutex::new(ChainTracker::new(depth));
+        self
+    }
+}
+
+#[async_trait]
+impl<B: IBlock> ICollector<ChainEvent<B>> for ReorgCollector<B> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, ChainEvent<B>>> {
+        let mut blocks = self.inner.get_event_stream().await?;
+
+        let stream = async_stream::stream! {
+            while let Some(block) = blocks.next().await {
+                let (number, hash) = (block.number(), block.hash());
+                let fetch = |hash| B::fetch_by_hash(self.provider.as_ref(), hash);
+
+                let chain = match resolve(&self.tracker, block, fetch).await {
+                    Ok(Some(chain)) => chain,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        error!("fail to resolve ancestors of block {number} ({hash}): {e:#}");
+                        continue;
+                    }
+                };
+
+                let events = self.tracker.lock().unwrap().apply(chain);
+                for event in events {
+                    yield event;
+                }
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Where a block stands relative to the known chain.
+#[derive(Debug, PartialEq)]
+enum Link {
+    /// Seen before.
+    Known,
+    /// Its parent is known, or no older block is: it can be applied as is.
+    Anchored,
+    /// Its parent has to be fetched first.
+    Detached,
+}
+
+/// The blocks `block` brings in, oldest first, starting with one that is anchored to the known chain if it could be
+/// found within the tracker's depth. `None` if `block` is already known.
+async fn resolve<B, F, Fut>(tracker: &Mutex<ChainTracker<B>>, block: B, mut fetch: F) -> Result<Option<Vec<B>>>
+where
+    B: IBlock,
+    F: FnMut(B256) -> Fut,
+    Fut: Future<Output = Result<Option<B>>>,
+{
+    let mut chain = vec![block];
+
+    loop {
+        let oldest = chain.last().unwrap();
+        let (link, depth) = {
+            let tracker = tracker.lock().unwrap();
+            (tracker.link(oldest), tracker.depth)
+        };
+
+        match link {
+            // Only `block` itself can be known: every other block in `chain` was fetched as the parent of a detached
+            // block, whose parent isn't known by definition.
+            Link::Known => return Ok(None),
+            Link::Anchored => break,
+            Link::Detached if chain.len() > depth => {
+                warn!(number = oldest.number(), depth, "no known ancestor within depth");
+                break;
+            }
+            Link::Detached => {
+                let parent = fetch(oldest.parent_hash())
+                    .await?
+                    .ok_or_eyre("parent block not found")?;
+                chain.push(parent);
+            }
+        }
+    }
+
+    chain.reverse();
+    Ok(Some(chain))
+}
+
+/// The last `depth` blocks of the canonical chain, oldest first.
+struct ChainTracker<B> {
+    blocks: VecDeque<B>,
+    depth: usize,
+}
+
+impl<B: IBlock> ChainTracker<B> {
+    fn new(depth: usize) -> Self {
+        Self {
+            blocks: VecDeque::new(),
+            depth: depth.max(1),
+        }
+    }
+
+    fn link(&self, block: &B) -> Link {
+        if self.blocks.iter().any(|known| known.hash() == block.hash()) {
+            return Link::Known;
+        }
+
+        let parent_known = self.blocks.iter().any(|known| known.hash() == block.parent_hash());
+        let beyond_window = self
+            .blocks
+            .front()
+            .is_none_or(|oldest| block.number() <= oldest.number());
+
+        if parent_known || beyond_window {
+            Link::Anchored
+        } else {
+            Link::Detached
+        }
+    }
+
+    /// Make `chain`, oldest first, the tip of the canonical chain.
+    fn apply(&mut self, chain: Vec<B>) -> Vec<ChainEvent<B>> {
+        let first = &chain[0];
+
+        // Everything from the first block's height is replaced. A chain that couldn't be anchored replaces the window
+        // without removing anything, since nothing is known to have been orphaned.
+        let fork = match self.link(first) {
+            Link::Anchored => self.blocks.partition_point(|known| known.number() < first.number()),
+            Link::Known | Link::Detached => {
+                self.blocks.clear();
+                0
+            }
+        };
+        let removed: Vec<B> = self.blocks.drain(fork..).collect();
+
+        self.blocks.extend(chain.iter().cloned());
+        while self.blocks.len() > self.depth {
+            self.blocks.pop_front();
+        }
+
+        if removed.is_empty() {
+            chain.into_iter().map(ChainEvent::NewBlock).collect()
+        } else {
+            warn!(
+                removed = removed.len(),
+                added = chain.len(),
+                tip = chain.last().map(|block| block.number()),
+                "chain reorganized"
+            );
+            vec![ChainEvent::Reorg { removed, added: chain }]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashMap, sync::Arc};
+
+    use alloy::{
+        primitives::B256,
+        providers::{Provider, ProviderBuilder},
+        transports::mock::Asserter,
+    };
+    use futures::StreamExt;
+
+    use super::{ChainEvent, IBlock, ReorgCollector};
+    use crate::{CollectorStream, ICollector};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct TestBlock {
+        number: u64,
+        hash: B256,
+        parent_hash: B256,
+    }
+
+    /// Block `number` of `fork`, whose parent is block `number - 1` of `parent_fork`.
+    fn block(number: u64, fork: u8, parent_fork: u8) -> TestBlock {
+        let hash = |number: u64, fork: u8| {
+            let mut hash = [0; 32];
+            hash[0] = fork;
+            hash[24..].copy_from_slice(&number.to_be_bytes());
+            B256::from(hash)
+        };
+        TestBlock {
+            number,
+            hash: hash(number, fork),
+            parent_hash: hash(number - 1, parent_fork),
+        }
+    }
+
+    thread_local! {
+        /// The blocks the node knows of, for `fetch_by_hash`.
+        static CHAIN: RefCell<HashMap<B256, TestBlock>> = RefCell::default();
+    }
+
+    #[async_trait::async_trait]
+    impl IBlock for TestBlock {
+        fn number(&self) -> u64 {
+            self.number
+        }
+
+        fn hash(&self) -> B256 {
+            self.hash
+        }
+
+        fn parent_hash(&self) -> B256 {
+            self.parent_hash
+        }
+
+        async fn fetch_by_hash(_provider: &dyn Provider, hash: B256) -> eyre::Result<Option<Self>> {
+            Ok(CHAIN.with_borrow(|chain| chain.get(&hash).cloned()))
+        }
+    }
+
+    struct TestBlocks(Vec<TestBlock>);
+
+    #[async_trait::async_trait]
+    impl ICollector<TestBlock> for TestBlocks {
+        async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, TestBlock>> {
+            Ok(Box::pin(futures::stream::iter(self.0.clone())))
+        }
+    }
+
+    /// Follow `blocks` with a collector keeping `depth` blocks, fetching ancestors from `known`.
+    async fn follow(depth: usize, known: &[TestBlock], blocks: Vec<TestBlock>) -> Vec<ChainEvent<TestBlock>> {
+        CHAIN.set(known.iter().map(|block| (block.hash, block.clone())).collect());
+
+        let provider: Arc<dyn Provider> = Arc::new(ProviderBuilder::new().connect_mocked_client(Asserter::new()));
+        let collector = ReorgCollector::new(Box::new(TestBlocks(blocks)), provider).with_depth(depth);
+        collector.get_event_stream().await.unwrap().collect().await
+    }
+    fn numbers(blocks: &[TestBlock]) -> Vec<(u64, u8)> {
+        blocks.iter().map(|block| (block.number, block.hash[0])).collect()
+    }
+
+    #[tokio::test]
+    async fn test_new_blocks_and_duplicates() {
+        let events = follow(
+            8,
+            &[],
+            vec![block(1, 0, 0), block(2, 0, 0), block(2, 0, 0), block(3, 0, 0)],
+        )
+        .await;
+
+        let numbers: Vec<u64> = events
+            .iter()
+            .map(|event| match event {
+                ChainEvent::NewBlock(block) => block.number,
+                ChainEvent::Reorg { .. } => panic!("unexpected reorg"),
+            })
+            .collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_skipped_blocks_are_filled_in() {
+        let known = [block(2, 0, 0), block(3, 0, 0)];
+        let events = follow(8, &known, vec![block(1, 0, 0), block(4, 0, 0)]).await;
+
+        assert_eq!(events.len(), 4);
+        assert!(matches!(&events[1], ChainEvent::NewBlock(block) if block.number == 2));
+        assert!(matches!(&events[3], ChainEvent::NewBlock(block) if block.number == 4));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_reports_removed_and_added_blocks() {
+        // 1 - 2 - 3 is replaced by 1 - 2' - 3' - 4', learned from 4' alone.
+        let known = [block(2, 1, 0), block(3, 1, 1)];
+        let blocks = vec![block(1, 0, 0), block(2, 0, 0), block(3, 0, 0), block(4, 1, 1)];
+        let events = follow(8, &known, blocks).await;
+
+        let ChainEvent::Reorg { removed, added } = &events[3] else {
+            panic!("expected a reorg, got {:?}", events[3]);
+        };
+        assert_eq!(numbers(removed), vec![(2, 0), (3, 0)]);
+        assert_eq!(numbers(added), vec![(2, 1), (3, 1), (4, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_deep_reorg_fetches_ancestors_by_hash() {
+        // 1 - ... - 6 is replaced by 1 - 2 - 3' - ... - 7', learned from 7' alone.
+        let known: Vec<_> = (3..=6)
+            .map(|number| block(number, 1, if number == 3 { 0 } else { 1 }))
+            .collect();
+        let mut blocks: Vec<_> = (1..=6).map(|number| block(number, 0, 0)).collect();
+        blocks.push(block(7, 1, 1));
+        let events = follow(8, &known, blocks).await;
+
+        assert_eq!(events.len(), 7);
+        let ChainEvent::Reorg { removed, added } = &events[6] else {
+            panic!("expected a reorg, got {:?}", events[6]);
+        };
+        assert_eq!(numbers(removed), vec![(3, 0), (4, 0), (5, 0), (6, 0)]);
+        assert_eq!(numbers(added), vec![(3, 1), (4, 1), (5, 1), (6, 1), (7, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_same_height_replacement_is_a_reorg() {
+        let events = follow(8, &[], vec![block(1, 0, 0), block(2, 0, 0), block(2, 1, 0)]).await;
+
+        let ChainEvent::Reorg { removed, added } = &events[2] else {
+            panic!("expected a reorg, got {:?}", events[2]);
+        };
+        assert_eq!(numbers(removed), vec![(2, 0)]);
+        assert_eq!(numbers(added), vec![(2, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_reorg_reaching_past_window_replaces_every_block() {
+        let known = [block(2, 1, 0), block(3, 1, 1), block(4, 1, 1)];
+        let blocks = vec![block(2, 0, 0), block(3, 0, 0), block(4, 0, 0), block(5, 1, 1)];
+        let events = follow(2, &known, blocks).await;
+
+        let ChainEvent::Reorg { removed, added } = events.last().unwrap() else {
+            panic!("expected a reorg");
+        };
+        assert_eq!(numbers(removed), vec![(3, 0), (4, 0)]);
+        assert_eq!(numbers(added), vec![(3, 1), (4, 1), (5, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_gap_longer_than_depth_starts_over() {
+        let known = [block(7, 0, 0), block(8, 0, 0), block(9, 0, 0)];
+        let blocks = vec![block(1, 0, 0), block(2, 0, 0), block(10, 0, 0), block(11, 0, 0)];
+        let events = follow(2, &known, blocks).await;
+
+        let numbers: Vec<u64> = events
+            .iter()
+            .map(|event| match event {
+                ChainEvent::NewBlock(block) => block.number,
+                ChainEvent::Reorg { .. } => panic!("unexpected reorg"),
+            })
+            .collect();
+        assert_eq!(numbers, vec![1, 2, 8, 9, 10, 11]);
+    }
+}
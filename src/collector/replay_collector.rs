@@ -0,0 +1,184 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use eyre::{WrapErr, bail};
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tracing::error;
+
+use super::RecordedEvent;
+use crate::{CollectorStream, ICollector};
+
+/// How fast a [`ReplayCollector`] plays back a recording.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReplaySpeed {
+    /// Keep the original delays between events.
+    #[default]
+    Original,
+    /// Divide the original delays by the given factor, which must be positive and finite.
+    Accelerated(f64),
+    /// Emit events back to back.
+    Unthrottled,
+}
+
+/// Plays back a recording made by [`RecordingCollector`](super::RecordingCollector). The collector is finite, so
+/// an engine whose collectors are all replays shuts down once they are done.
+pub struct ReplayCollector<E> {
+    path: PathBuf,
+    speed: ReplaySpeed,
+    since_us: Option<u64>,
+    _phantom: std::marker::PhantomData<fn() -> E>,
+}
+
+impl<E> ReplayCollector<E> {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            speed: ReplaySpeed::default(),
+            since_us: None,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: ReplaySpeed) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    /// Skip events recorded before `timestamp_us` (microseconds since the unix epoch).
+    pub fn since(mut self, timestamp_us: u64) -> Self {
+        self.since_us = Some(timestamp_us);
+        self
+    }
+
+    /// Skip events recorded before `time`.
+    pub fn since_time(self, time: SystemTime) -> Self {
+        let timestamp_us = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or_default();
+        self.since(timestamp_us)
+    }
+
+    fn delay(&self, from_us: u64, to_us: u64) -> Option<Duration> {
+        let delay = Duration::from_micros(to_us.saturating_sub(from_us));
+
+        match self.speed {
+            ReplaySpeed::Original => Some(delay),
+            ReplaySpeed::Accelerated(factor) => Some(delay.div_f64(factor)),
+            ReplaySpeed::Unthrottled => None,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for ReplayCollector<E>
+where
+    E: DeserializeOwned + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        "Replay Collector"
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<CollectorStream<'_, E>> {
+        if let ReplaySpeed::Accelerated(factor) = self.speed
+            && !(factor.is_finite() && factor > 0.0)
+        {
+            bail!("invalid replay speed factor {factor}, must be positive and finite");
+        }
+
+        let file = tokio::fs::File::open(&self.path)
+            .await
+            .wrap_err_with(|| format!("fail to open recording file {}", self.path.display()))?;
+        let mut lines = BufReader::new(file).lines();
+
+        let stream = async_stream::stream! {
+            let mut previous_us = None;
+            let mut number = 0;
+
+            loop {
+                number += 1;
+
+                let line = match lines.next_line().await {
+                    Ok(Some(line)) if line.trim().is_empty() => continue,
+                    Ok(Some(line)) => line,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("fail to read recording: {e:#}");
+                        break;
+                    }
+                };
+
+                let record: RecordedEvent<E> = match serde_json::from_str(&line) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        error!(line = number, "fail to parse recorded event: {e:#}");
+                        continue;
+                    }
+                };
+
+                if self.since_us.is_some_and(|since| record.timestamp_us < since) {
+                    continue;
+                }
+
+                if let Some(delay) = previous_us.and_then(|previous| self.delay(previous, record.timestamp_us)) {
+                    tokio::time::sleep(delay).await;
+                }
+
+                previous_us = Some(record.timestamp_us);
+                yield record.event;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+
+    use super::{ReplayCollector, ReplaySpeed};
+    use crate::{ICollector, collector::RecordingCollector};
+
+    struct VecCollector(Vec<u64>);
+
+    #[async_trait::async_trait]
+    impl ICollector<u64> for VecCollector {
+        async fn get_event_stream(&self) -> eyre::Result<crate::CollectorStream<'_, u64>> {
+            Ok(Box::pin(futures::stream::iter(self.0.clone())))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path = std::env::temp_dir().join(format!("harpoon-replay-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let recorder = RecordingCollector::new(Box::new(VecCollector(vec![1, 2, 3])), &path);
+        let recorded: Vec<u64> = recorder.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(recorded, vec![1, 2, 3]);
+
+        let replay = ReplayCollector::<u64>::new(&path).with_speed(ReplaySpeed::Unthrottled);
+        let replayed: Vec<u64> = replay.get_event_stream().await.unwrap().collect().await;
+        assert_eq!(replayed, vec![1, 2, 3]);
+
+        let replay = ReplayCollector::<u64>::new(&path).since(u64::MAX);
+        let replayed: Vec<u64> = replay.get_event_stream().await.unwrap().collect().await;
+        assert!(replayed.is_empty());
+
+        for factor in [0.0, -2.0, f64::NAN] {
+            let replay = ReplayCollector::<u64>::new(&path).with_speed(ReplaySpeed::Accelerated(factor));
+            assert!(replay.get_event_stream().await.is_err());
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
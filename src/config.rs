@@ -0,0 +1,250 @@
+//! Build an [`Engine`](crate::Engine) from a TOML or YAML file instead of wiring it by hand.
+//!
+//! The file lists providers, the built-in collectors and executors, and strategies by the name they were
+//! registered under in a [`StrategyRegistry`]. Secrets such as private keys and bot tokens are never written in the
+//! file itself; it names the environment variables holding them.
+//!
+//! ```toml
+//! [engine]
+//! event_channel_capacity = 1024
+//! shutdown_on_signal = true
+//! metrics_address = "127.0.0.1:9100"
+//!
+//! [providers.mainnet]
+//! url = "wss://eth.merkle.io"
+//!
+//! [[collectors]]
+//! type = "block"
+//! provider = "mainnet"
+//!
+//! [[collectors]]
+//! type = "log"
+//! provider = "mainnet"
+//! address = ["0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"]
+//! event = ["Transfer(address,address,uint256)"]
+//!
+//! [[executors]]
+//! type = "transaction"
+//! provider = "mainnet"
+//! submission_url = "https://rpc.flashbots.net/fast"
+//! signer_key_envs = ["SEARCHER_KEY"]
+//! concurrency = 4
+//!
+//! [[strategies]]
+//! name = "arbitrage"
+//! params = { min_profit = 0.05 }
+//! ```
+
+use std::{collections::HashMap, net::SocketAddr, path::Path, time::Duration};
+
+use alloy::primitives::{Address, B256};
+use eyre::{Context, bail};
+use serde::{
+    Deserialize, Deserializer,
+    de::{DeserializeOwned, Error},
+};
+
+use crate::engine::OverflowPolicy;
+
+mod loader;
+mod registry;
+
+pub use loader::EngineLoader;
+pub use registry::{StrategyFactory, StrategyRegistry};
+
+/// The contents of an engine config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EngineConfig {
+    #[serde(default)]
+    pub engine: EngineSettings,
+    /// RPC endpoints, referenced by name from collectors and executors.
+    #[serde(default)]
+    pub providers: HashMap<String, ProviderSpec>,
+    #[serde(default)]
+    pub collectors: Vec<CollectorSpec>,
+    #[serde(default)]
+    pub executors: Vec<ExecutorSpec>,
+    #[serde(default)]
+    pub strategies: Vec<StrategySpec>,
+}
+
+impl EngineConfig {
+    /// Read a config file, parsed as YAML if its extension is `yaml` or `yml` and as TOML otherwise.
+    pub fn from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let content =
+            std::fs::read_to_string(path).wrap_err_with(|| format!("fail to read config {}", path.display()))?;
+
+        let config = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml" | "yml") => Self::from_yaml_str(&content),
+            _ => Self::from_toml_str(&content),
+        };
+
+        config.wrap_err_with(|| format!("invalid config {}", path.display()))
+    }
+
+    pub fn from_toml_str(content: &str) -> eyre::Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_yaml_str(content: &str) -> eyre::Result<Self> {
+        Ok(serde_yaml_ng::from_str(content)?)
+    }
+}
+
+/// Engine-wide settings; anything left out keeps the [`Engine`](crate::Engine) default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EngineSettings {
+    pub event_channel_capacity: Option<usize>,
+    pub action_channel_capacity: Option<usize>,
+    pub shutdown_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub shutdown_on_signal: bool,
+    /// Where to serve Prometheus metrics, e.g. `127.0.0.1:9100`.
+    pub metrics_address: Option<SocketAddr>,
+}
+
+impl EngineSettings {
+    pub fn shutdown_timeout(&self) -> Option<Duration> {
+        self.shutdown_timeout_ms.map(Duration::from_millis)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderSpec {
+    /// `ws://`, `wss://`, `http://`, `https://` or an IPC path. Collectors need a pubsub (ws or IPC) endpoint.
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum CollectorSpec {
+    /// A [`BlockCollector`](crate::collector::BlockCollector).
+    Block { provider: String },
+
+    /// A [`LogCollector`](crate::collector::LogCollector). Every field left empty matches anything.
+    Log {
+        provider: String,
+        #[serde(default)]
+        address: Vec<Address>,
+        /// Event signatures such as `Transfer(address,address,uint256)`, matched against topic 0.
+        #[serde(default)]
+        event: Vec<String>,
+        #[serde(default)]
+        topic1: Vec<B256>,
+        #[serde(default)]
+        topic2: Vec<B256>,
+        #[serde(default)]
+        topic3: Vec<B256>,
+    },
+
+    /// A [`MempoolCollector`](crate::collector::MempoolCollector).
+    Mempool { provider: String },
+
+    /// An [`IntervalCollector`](crate::collector::IntervalCollector).
+    Interval { interval_ms: u64 },
+}
+
+impl CollectorSpec {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Block { .. } => "block",
+            Self::Log { .. } => "log",
+            Self::Mempool { .. } => "mempool",
+            Self::Interval { .. } => "interval",
+        }
+    }
+}
+
+/// An executor along with its queue settings; see [`ExecutorConfig`](crate::engine::ExecutorConfig).
+#[derive(Debug, Clone)]
+pub struct ExecutorSpec {
+    pub kind: ExecutorKind,
+    pub capacity: Option<usize>,
+    pub overflow: Option<OverflowPolicy>,
+    pub concurrency: Option<usize>,
+}
+
+impl<'de> Deserialize<'de> for ExecutorSpec {
+    // `flatten` ignores `deny_unknown_fields`, so the queue settings are taken out by hand and the remaining fields
+    // must match the executor kind exactly.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut fields = serde_json::Map::deserialize(deserializer)?;
+        let capacity = take_field(&mut fields, "capacity")?;
+        let overflow = take_field(&mut fields, "overflow")?;
+        let concurrency = take_field(&mut fields, "concurrency")?;
+        let kind = ExecutorKind::deserialize(serde_json::Value::Object(fields)).map_err(D::Error::custom)?;
+
+        Ok(Self {
+            kind,
+            capacity,
+            overflow,
+            concurrency,
+        })
+    }
+}
+
+fn take_field<T: DeserializeOwned, E: Error>(
+    fields: &mut serde_json::Map<String, serde_json::Value>,
+    key: &str,
+) -> Result<Option<T>, E> {
+    fields
+        .remove(key)
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| E::custom(format!("invalid {key}: {e}")))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ExecutorKind {
+    /// A [`TransactionSender`](crate::executor::transaction::TransactionSender) signing with the private keys held
+    /// in `signer_key_envs`, and sending through `submission_url` instead of `provider` if set.
+    Transaction {
+        provider: String,
+        submission_url: Option<String>,
+        #[serde(default)]
+        signer_key_envs: Vec<String>,
+    },
+
+    /// A [`RawTransactionSender`](crate::executor::raw_transaction::RawTransactionSender) posting to `url`.
+    RawTransaction { url: String },
+
+    /// A [`TelegramMessageDispatcher`](crate::executor::telegram_message::TelegramMessageDispatcher), reporting
+    /// failed messages with the bot token held in `error_report_bot_token_env` if set.
+    Telegram {
+        error_report_bot_token_env: Option<String>,
+        error_report_chat_id: Option<String>,
+        error_report_thread_id: Option<String>,
+    },
+}
+
+impl ExecutorKind {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Transaction { .. } => "transaction",
+            Self::RawTransaction { .. } => "raw_transaction",
+            Self::Telegram { .. } => "telegram",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategySpec {
+    /// Name the strategy was registered under in the [`StrategyRegistry`].
+    pub name: String,
+    /// Passed as-is to the strategy's factory.
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+fn env_var(name: &str) -> eyre::Result<String> {
+    match std::env::var(name) {
+        Ok(value) => Ok(value),
+        Err(_) => bail!("environment variable {name} is not set"),
+    }
+}
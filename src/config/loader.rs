@@ -0,0 +1,562 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    primitives::Bytes,
+    providers::{Provider, ProviderBuilder},
+    rpc::types::{
+        Header,
+        eth::{Filter, Log, Transaction, TransactionRequest},
+    },
+    signers::local::PrivateKeySigner,
+};
+use eyre::{Context, bail, eyre};
+use tracing::info;
+
+use super::{CollectorSpec, EngineConfig, ExecutorKind, ExecutorSpec, StrategyRegistry, env_var};
+use crate::{
+    CollectorMap, Engine, ExecutorMap, ICollector, IExecutor, IStrategy,
+    collector::{BlockCollector, IntervalCollector, LogCollector, MempoolCollector},
+    engine::ExecutorConfig,
+    executor::{
+        raw_transaction::RawTransactionSender,
+        telegram_message::{Message, TelegramMessageDispatcher},
+        transaction::TransactionSender,
+    },
+};
+
+type EventMapper<T, E> = Arc<dyn Fn(T) -> E + Send + Sync>;
+type ActionMapper<A, T> = Arc<dyn Fn(A) -> Option<T> + Send + Sync>;
+
+/// Turns an [`EngineConfig`] into an [`Engine`].
+///
+/// A config only names built-in collectors and executors, so the loader has to be told how their events fit into
+/// the engine's event type and which actions each executor handles, the same way `map_collector!` and
+/// `map_executor!` do when wiring by hand. Loading fails if the config uses a collector or executor without a
+/// mapping, or a strategy missing from the registry.
+pub struct EngineLoader<E, A> {
+    blocks: Option<EventMapper<Header, E>>,
+    logs: Option<EventMapper<Log, E>>,
+    transactions: Option<EventMapper<Transaction, E>>,
+    ticks: Option<EventMapper<Instant, E>>,
+
+    transaction_requests: Option<ActionMapper<A, TransactionRequest>>,
+    raw_transactions: Option<ActionMapper<A, Bytes>>,
+    messages: Option<ActionMapper<A, Message>>,
+
+    strategies: StrategyRegistry<E, A>,
+}
+
+impl<E, A> EngineLoader<E, A>
+where
+    E: Send + Sync + Clone + std::fmt::Debug + 'static,
+    A: Send + Sync + Clone + std::fmt::Debug + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            blocks: None,
+            logs: None,
+            transactions: None,
+            ticks: None,
+            transaction_requests: None,
+            raw_transactions: None,
+            messages: None,
+            strategies: StrategyRegistry::new(),
+        }
+    }
+
+    /// Events of `block` collectors.
+    pub fn on_block(mut self, f: impl Fn(Header) -> E + Send + Sync + 'static) -> Self {
+        self.blocks = Some(Arc::new(f));
+        self
+    }
+
+    /// Events of `log` collectors.
+    pub fn on_log(mut self, f: impl Fn(Log) -> E + Send + Sync + 'static) -> Self {
+        self.logs = Some(Arc::new(f));
+        self
+    }
+
+    /// Events of `mempool` collectors.
+    pub fn on_transaction(mut self, f: impl Fn(Transaction) -> E + Send + Sync + 'static) -> Self {
+        self.transactions = Some(Arc::new(f));
+        self
+    }
+
+    /// Events of `interval` collectors.
+    pub fn on_tick(mut self, f: impl Fn(Instant) -> E + Send + Sync + 'static) -> Self {
+        self.ticks = Some(Arc::new(f));
+        self
+    }
+
+    /// Actions handled by `transaction` executors.
+    pub fn send_transactions(mut self, f: impl Fn(A) -> Option<TransactionRequest> + Send + Sync + 'static) -> Self {
+        self.transaction_requests = Some(Arc::new(f));
+        self
+    }
+
+    /// Actions handled by `raw_transaction` executors.
+    pub fn send_raw_transactions(mut self, f: impl Fn(A) -> Option<Bytes> + Send + Sync + 'static) -> Self {
+        self.raw_transactions = Some(Arc::new(f));
+        self
+    }
+
+    /// Actions handled by `telegram` executors.
+    pub fn send_messages(mut self, f: impl Fn(A) -> Option<Message> + Send + Sync + 'static) -> Self {
+        self.messages = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_strategy_registry(mut self, registry: StrategyRegistry<E, A>) -> Self {
+        self.strategies = registry;
+        self
+    }
+
+    /// Shorthand for registering a single strategy in the loader's registry.
+    pub fn register_strategy<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: Fn(&serde_json::Value) -> eyre::Result<Box<dyn IStrategy<E, A>>> + Send + Sync + 'static,
+    {
+        self.strategies.register(name, factory);
+        self
+    }
+
+    pub async fn load_file(&self, path: impl AsRef<Path>) -> eyre::Result<Engine<E, A>> {
+        self.load(&EngineConfig::from_file(path)?).await
+    }
+
+    /// Build the engine described by `config`, connecting to every provider it uses.
+    pub async fn load(&self, config: &EngineConfig) -> eyre::Result<Engine<E, A>> {
+        // Check everything that doesn't need a connection first, so a typo doesn't wait on the network.
+        self.validate(config)?;
+
+        let mut engine = Engine::new();
+        let settings = &config.engine;
+
+        if let Some(capacity) = settings.event_channel_capacity {
+            engine = engine.with_event_channel_capacity(capacity);
+        }
+        if let Some(capacity) = settings.action_channel_capacity {
+            engine = engine.with_action_channel_capacity(capacity);
+        }
+        if let Some(timeout) = settings.shutdown_timeout() {
+            engine = engine.with_shutdown_timeout(timeout);
+        }
+        if settings.shutdown_on_signal {
+            engine = engine.with_shutdown_on_signal();
+        }
+        if let Some(address) = settings.metrics_address {
+            engine = engine.with_metrics_endpoint(address);
+        }
+
+        let mut providers = Providers::new(config);
+
+        for spec in &config.collectors {
+            let collector = self
+                .build_collector(spec, &mut providers)
+                .await
+                .wrap_err_with(|| format!("fail to build {} collector", spec.kind()))?;
+            engine.add_collector(collector);
+        }
+
+        for spec in &config.executors {
+            let executor = self
+                .build_executor(spec, &mut providers)
+                .await
+                .wrap_err_with(|| format!("fail to build {} executor", spec.kind.kind()))?;
+            engine.add_executor_with_config(executor, executor_config(spec));
+        }
+
+        for spec in &config.strategies {
+            let strategy = self
+                .strategies
+                .create(&spec.name, &spec.params)
+                .wrap_err_with(|| format!("fail to build strategy {}", spec.name))?;
+            engine.add_strategy(strategy);
+        }
+
+        info!(
+            collectors = config.collectors.len(),
+            strategies = config.strategies.len(),
+            executors = config.executors.len(),
+            "engine loaded from config"
+        );
+
+        Ok(engine)
+    }
+
+    fn validate(&self, config: &EngineConfig) -> eyre::Result<()> {
+        for spec in &config.collectors {
+            let mapped = match spec {
+                CollectorSpec::Block { .. } => self.blocks.is_some(),
+                CollectorSpec::Log { .. } => self.logs.is_some(),
+                CollectorSpec::Mempool { .. } => self.transactions.is_some(),
+                CollectorSpec::Interval { .. } => self.ticks.is_some(),
+            };
+
+            if !mapped {
+                bail!(
+                    "{} collector configured but no event mapping was given for it",
+                    spec.kind()
+                );
+            }
+
+            match spec {
+                CollectorSpec::Block { provider }
+                | CollectorSpec::Log { provider, .. }
+                | CollectorSpec::Mempool { provider } => check_provider(config, provider)?,
+                CollectorSpec::Interval { .. } => {}
+            }
+        }
+
+        for spec in &config.executors {
+            let mapped = match &spec.kind {
+                ExecutorKind::Transaction { provider, .. } => {
+                    check_provider(config, provider)?;
+                    self.transaction_requests.is_some()
+                }
+                ExecutorKind::RawTransaction { .. } => self.raw_transactions.is_some(),
+                ExecutorKind::Telegram { .. } => self.messages.is_some(),
+            };
+
+            if !mapped {
+                bail!(
+                    "{} executor configured but no action mapping was given for it",
+                    spec.kind.kind()
+                );
+            }
+        }
+
+        for spec in &config.strategies {
+            if !self.strategies.contains(&spec.name) {
+                bail!("no strategy registered as `{}`", spec.name);
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn build_collector(
+        &self,
+        spec: &CollectorSpec,
+        providers: &mut Providers<'_>,
+    ) -> eyre::Result<Box<dyn ICollector<E>>> {
+        let collector: Box<dyn ICollector<E>> = match spec {
+            CollectorSpec::Block { provider } => {
+                let collector = BlockCollector::new(providers.get(provider).await?);
+                map_collector(Box::new(collector), self.blocks.clone())
+            }
+            CollectorSpec::Log {
+                provider,
+                address,
+                event,
+                topic1,
+                topic2,
+                topic3,
+            } => {
+                let mut filter = Filter::new();
+                if !address.is_empty() {
+                    filter = filter.address(address.clone());
+                }
+                if !event.is_empty() {
+                    filter = filter.events(event);
+                }
+                if !topic1.is_empty() {
+                    filter = filter.topic1(topic1.clone());
+                }
+                if !topic2.is_empty() {
+                    filter = filter.topic2(topic2.clone());
+                }
+                if !topic3.is_empty() {
+                    filter = filter.topic3(topic3.clone());
+                }
+
+                let collector = LogCollector::new(providers.get(provider).await?, filter);
+                map_collector(Box::new(collector), self.logs.clone())
+            }
+            CollectorSpec::Mempool { provider } => {
+                let collector = MempoolCollector::new(providers.get(provider).await?);
+                map_collector(Box::new(collector), self.transactions.clone())
+            }
+            CollectorSpec::Interval { interval_ms } => {
+                let collector = IntervalCollector::new(Duration::from_millis(*interval_ms));
+                map_collector(Box::new(collector), self.ticks.clone())
+            }
+        };
+
+        Ok(collector)
+    }
+
+    async fn build_executor(
+        &self,
+        spec: &ExecutorSpec,
+        providers: &mut Providers<'_>,
+    ) -> eyre::Result<Box<dyn IExecutor<A>>> {
+        let executor = match &spec.kind {
+            ExecutorKind::Transaction {
+                provider,
+                submission_url,
+                signer_key_envs,
+            } => {
+                let signers = signer_key_envs
+                    .iter()
+                    .map(|name| {
+                        env_var(name)?
+                            .trim()
+                            .parse::<PrivateKeySigner>()
+                            .wrap_err_with(|| format!("invalid private key in {name}"))
+                    })
+                    .collect::<eyre::Result<Vec<_>>>()?;
+
+                let provider = providers.get(provider).await?;
+
+                let executor = match submission_url {
+                    Some(url) => TransactionSender::new_with_dedicated_tx_submission_endpoint(
+                        provider,
+                        http_provider(url)?,
+                        signers,
+                    ),
+                    None => TransactionSender::new(provider, signers),
+                };
+
+                map_executor(Box::new(executor), self.transaction_requests.clone())
+            }
+            ExecutorKind::RawTransaction { url } => {
+                let executor = RawTransactionSender::new(http_provider(url)?);
+                map_executor(Box::new(executor), self.raw_transactions.clone())
+            }
+            ExecutorKind::Telegram {
+                error_report_bot_token_env,
+                error_report_chat_id,
+                error_report_thread_id,
+            } => {
+                let bot_token = error_report_bot_token_env.as_deref().map(env_var).transpose()?;
+                let executor = TelegramMessageDispatcher::new_with_error_report(
+                    bot_token,
+                    error_report_chat_id.clone(),
+                    error_report_thread_id.clone(),
+                );
+                map_executor(Box::new(executor), self.messages.clone())
+            }
+        };
+
+        Ok(executor)
+    }
+}
+
+impl<E, A> Default for EngineLoader<E, A>
+where
+    E: Send + Sync + Clone + std::fmt::Debug + 'static,
+    A: Send + Sync + Clone + std::fmt::Debug + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn map_collector<T, E>(collector: Box<dyn ICollector<T>>, f: Option<EventMapper<T, E>>) -> Box<dyn ICollector<E>>
+where
+    T: Send + Sync + 'static,
+    E: Send + Sync + 'static,
+{
+    let f = f.expect("event mappings are validated before building");
+    Box::new(CollectorMap::new(collector, move |event| f(event)))
+}
+
+fn map_executor<A, T>(executor: Box<dyn IExecutor<T>>, f: Option<ActionMapper<A, T>>) -> Box<dyn IExecutor<A>>
+where
+    A: Send + Sync + 'static,
+    T: Send + Sync + 'static,
+{
+    let f = f.expect("action mappings are validated before building");
+    Box::new(ExecutorMap::new(executor, move |action| f(action)))
+}
+
+fn executor_config(spec: &ExecutorSpec) -> ExecutorConfig {
+    let mut config = ExecutorConfig::default();
+
+    if let Some(capacity) = spec.capacity {
+        config = config.with_capacity(capacity);
+    }
+    if let Some(overflow) = spec.overflow {
+        config = config.with_overflow(overflow);
+    }
+    if let Some(concurrency) = spec.concurrency {
+        config = config.with_concurrency(concurrency);
+    }
+
+    config
+}
+
+fn check_provider(config: &EngineConfig, name: &str) -> eyre::Result<()> {
+    if !config.providers.contains_key(name) {
+        bail!("unknown provider `{name}`");
+    }
+    Ok(())
+}
+
+fn http_provider(url: &str) -> eyre::Result<Arc<dyn Provider>> {
+    let url = url.parse().wrap_err_with(|| format!("invalid url {url}"))?;
+    Ok(Arc::new(ProviderBuilder::default().connect_http(url)))
+}
+
+/// Connects to each provider of the config at most once, and only if something uses it.
+struct Providers<'a> {
+    config: &'a EngineConfig,
+    connected: HashMap<&'a str, Arc<dyn Provider>>,
+}
+
+impl<'a> Providers<'a> {
+    fn new(config: &'a EngineConfig) -> Self {
+        Self {
+            config,
+            connected: HashMap::new(),
+        }
+    }
+
+    async fn get(&mut self, name: &str) -> eyre::Result<Arc<dyn Provider>> {
+        if let Some(provider) = self.connected.get(name) {
+            return Ok(provider.clone());
+        }
+
+        let (name, spec) = self
+            .config
+            .providers
+            .get_key_value(name)
+            .ok_or_else(|| eyre!("unknown provider `{name}`"))?;
+
+        let provider = ProviderBuilder::new()
+            .connect(&spec.url)
+            .await
+            .wrap_err_with(|| format!("fail to connect to provider {name}"))?;
+        let provider: Arc<dyn Provider> = Arc::new(provider);
+
+        self.connected.insert(name, provider.clone());
+        Ok(provider)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Instant};
+
+    use super::EngineLoader;
+    use crate::{IActionSubmitter, IStrategy, config::EngineConfig, executor::telegram_message::Message};
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    enum Event {
+        Tick(Instant),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, Clone)]
+    enum Action {
+        Notify(Message),
+    }
+
+    struct Noop;
+
+    #[async_trait::async_trait]
+    impl IStrategy<Event, Action> for Noop {
+        async fn process_event(&mut self, _event: Event, _submitter: Arc<dyn IActionSubmitter<Action>>) {}
+    }
+
+    fn loader() -> EngineLoader<Event, Action> {
+        EngineLoader::new()
+            .on_tick(Event::Tick)
+            .send_messages(|action| match action {
+                Action::Notify(message) => Some(message),
+            })
+            .register_strategy("noop", |params| {
+                assert_eq!(params["threshold"], 3);
+                Ok(Box::new(Noop))
+            })
+    }
+
+    const CONFIG: &str = r#"
+        [engine]
+        event_channel_capacity = 16
+
+        [[collectors]]
+        type = "interval"
+        interval_ms = 1000
+
+        [[executors]]
+        type = "telegram"
+        error_report_chat_id = "42"
+        concurrency = 2
+        overflow = "drop_oldest"
+
+        [[strategies]]
+        name = "noop"
+        params = { threshold = 3 }
+    "#;
+
+    #[tokio::test]
+    async fn test_load_toml() {
+        let config = EngineConfig::from_toml_str(CONFIG).unwrap();
+        let engine = loader().load(&config).await.unwrap();
+
+        assert_eq!(engine.strategy_count(), 1);
+        assert_eq!(engine.executor_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_load_yaml() {
+        let config = EngineConfig::from_yaml_str(
+            r#"
+            collectors:
+              - type: interval
+                interval_ms: 1000
+            strategies:
+              - name: noop
+                params:
+                  threshold: 3
+            "#,
+        )
+        .unwrap();
+        let engine = loader().load(&config).await.unwrap();
+
+        assert_eq!(engine.strategy_count(), 1);
+        assert_eq!(engine.executor_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_load_rejects_incomplete_wiring() {
+        let config = EngineConfig::from_toml_str("[[strategies]]\nname = \"missing\"").unwrap();
+        let err = loader().load(&config).await.err().unwrap();
+        assert!(err.to_string().contains("missing"));
+
+        let config = EngineConfig::from_toml_str("[[collectors]]\ntype = \"block\"\nprovider = \"mainnet\"").unwrap();
+        assert!(loader().load(&config).await.is_err());
+
+        let config = EngineConfig::from_toml_str("[[collectors]]\ntype = \"interval\"\ninterval_ms = 1").unwrap();
+        assert!(EngineLoader::<Event, Action>::new().load(&config).await.is_err());
+    }
+
+    #[test]
+    fn test_reject_unknown_executor_fields() {
+        let config = r#"
+            [[executors]]
+            type = "transaction"
+            provider = "mainnet"
+            submision_url = "https://rpc.flashbots.net/fast"
+        "#;
+        let err = EngineConfig::from_toml_str(config).unwrap_err();
+        assert!(err.to_string().contains("submision_url"), "{err}");
+
+        let config = "executors:\n  - type: telegram\n    concurency: 2\n";
+        let err = EngineConfig::from_yaml_str(config).unwrap_err();
+        assert!(err.to_string().contains("concurency"), "{err}");
+
+        let config = "[[executors]]\ntype = \"raw_transaction\"\nurl = \"http://localhost\"\ncapacity = 8";
+        let spec = &EngineConfig::from_toml_str(config).unwrap().executors[0];
+        assert_eq!(spec.capacity, Some(8));
+        assert_eq!(spec.kind.kind(), "raw_transaction");
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use eyre::eyre;
+
+use crate::IStrategy;
+
+/// Builds a strategy from the `params` of its config entry.
+pub type StrategyFactory<E, A> =
+    Box<dyn Fn(&serde_json::Value) -> eyre::Result<Box<dyn IStrategy<E, A>>> + Send + Sync>;
+
+/// Strategies a config file can refer to by name.
+pub struct StrategyRegistry<E, A> {
+    factories: HashMap<String, StrategyFactory<E, A>>,
+}
+
+impl<E, A> StrategyRegistry<E, A> {
+    pub fn new() -> Self {
+        Self {
+            factories: HashMap::new(),
+        }
+    }
+
+    /// Register `factory` under `name`, replacing any factory previously registered under the same name.
+    pub fn register<F>(&mut self, name: impl Into<String>, factory: F)
+    where
+        F: Fn(&serde_json::Value) -> eyre::Result<Box<dyn IStrategy<E, A>>> + Send + Sync + 'static,
+    {
+        self.factories.insert(name.into(), Box::new(factory));
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(name)
+    }
+
+    pub fn create(&self, name: &str, params: &serde_json::Value) -> eyre::Result<Box<dyn IStrategy<E, A>>> {
+        let factory = self
+            .factories
+            .get(name)
+            .ok_or_else(|| eyre!("no strategy registered as `{name}`"))?;
+
+        factory(params)
+    }
+}
+
+impl<E, A> Default for StrategyRegistry<E, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
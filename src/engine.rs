@@ -1,21 +1,90 @@
 use eyre::Context;
-use futures::StreamExt;
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    net::SocketAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize},
+    },
+    time::Duration,
+};
 use tokio::{
-    sync::broadcast::{self, Sender, error::RecvError},
+    sync::{
+        Notify,
+        broadcast::{self, Sender},
+        mpsc,
+    },
     task::JoinSet,
 };
-use tracing::{debug, error, warn};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+use crate::{
+    Clock, CollectorMap, ExecutorMap, IActionSubmitter, ICollector, IExecutor, IStrategy, IVariant, StrategyMap,
+};
 
-use crate::{ICollector, IExecutor, IStrategy, action_submitter::ActionChannelSubmitter};
+mod envelope;
+mod event;
+mod executor;
+mod handle;
+mod metrics;
+mod panic;
+mod queue;
+mod report;
+mod shard;
+mod shutdown;
+mod snapshot;
+mod strategy;
+mod supervisor;
+pub(crate) mod trace;
+
+pub use envelope::{Envelope, EventMeta};
+pub use event::EngineEvent;
+pub use executor::ExecutorConfig;
+pub use handle::{ComponentKind, ComponentState, ComponentStatus, EngineHandle};
+pub use metrics::EngineMetrics;
+pub use panic::PanicPolicy;
+pub use queue::OverflowPolicy;
+pub use report::ExecutionReport;
+pub use shard::ShardKey;
+pub use shutdown::ShutdownHandle;
+pub(crate) use snapshot::file_stem;
+pub use snapshot::{FileSnapshotStore, ISnapshotStore, Snapshot, SnapshotConfig};
+pub use supervisor::RestartPolicy;
+pub use trace::CorrelationId;
+
+use executor::ExecutorContext;
+use handle::{AddedTasks, Runtime};
+use queue::{ActionQueue, ActionRouter, StrategySubmitter};
+use shard::Shard;
+use strategy::{StrategyContext, StrategyInputs};
+use supervisor::CollectorContext;
+
+type StrategyEntry<E, A> = (Box<dyn IStrategy<E, A>>, Option<PanicPolicy>, Option<Shard>);
 
 pub struct Engine<E, A> {
-    collectors: Vec<Box<dyn ICollector<E>>>,
-    strategies: Vec<Box<dyn IStrategy<E, A>>>,
-    executors: Vec<Box<dyn IExecutor<A>>>,
+    collectors: Vec<(Box<dyn ICollector<E>>, Option<RestartPolicy>)>,
+    strategies: Vec<StrategyEntry<E, A>>,
+    executors: Vec<(Box<dyn IExecutor<A>>, Option<ExecutorConfig>)>,
 
     event_channel_capacity: usize,
     action_channel_capacity: usize,
+    engine_event_channel_capacity: usize,
+
+    restart_policy: RestartPolicy,
+    panic_policy: PanicPolicy,
+    clock: Clock,
+
+    shutdown: ShutdownHandle,
+    shutdown_timeout: Duration,
+    shutdown_on_signal: bool,
+
+    handle: EngineHandle<E, A>,
+
+    metrics: EngineMetrics,
+    metrics_address: Option<SocketAddr>,
+
+    snapshots: Option<SnapshotConfig<E>>,
 }
 
 impl<E, A> Engine<E, A> {
@@ -26,6 +95,17 @@ impl<E, A> Engine<E, A> {
             executors: vec![],
             event_channel_capacity: 512,
             action_channel_capacity: 512,
+            engine_event_channel_capacity: 64,
+            restart_policy: RestartPolicy::default(),
+            panic_policy: PanicPolicy::default(),
+            clock: Clock::System,
+            shutdown: ShutdownHandle::new(),
+            shutdown_timeout: Duration::from_secs(30),
+            shutdown_on_signal: false,
+            handle: EngineHandle::new(),
+            metrics: EngineMetrics::new(),
+            metrics_address: None,
+            snapshots: None,
         }
     }
 
@@ -34,11 +114,79 @@ impl<E, A> Engine<E, A> {
         self
     }
 
+    /// Capacity of the action queue of executors added without an [`ExecutorConfig`].
     pub fn with_action_channel_capacity(mut self, capacity: usize) -> Self {
         self.action_channel_capacity = capacity;
         self
     }
 
+    /// Restart policy for collectors added without one of their own.
+    pub fn with_restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = policy;
+        self
+    }
+
+    /// Panic policy for strategies and executors added without one of their own.
+    pub fn with_panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// Run against `clock` instead of the system clock. With a [`SimulatedClock`](crate::SimulatedClock), the
+    /// engine backtests: it shuts down once every finite collector (e.g. a historical block collector driving the
+    /// clock) has finished, even if others such as a `SimulatedIntervalCollector` following the clock are still running.
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The engine's time source, to share with strategies that keep timers.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// How long strategies and executors may keep draining after a shutdown is requested before they are
+    /// aborted.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Request a graceful shutdown when the process receives SIGINT or SIGTERM.
+    pub fn with_shutdown_on_signal(mut self) -> Self {
+        self.shutdown_on_signal = true;
+        self
+    }
+
+    /// Persist the state of strategies that implement [`IStrategy::snapshot`], and restore it before
+    /// [`IStrategy::sync_state`] when the engine starts again. Strategies added through the [`EngineHandle`] aren't
+    /// snapshotted.
+    pub fn with_snapshots(mut self, config: SnapshotConfig<E>) -> Self {
+        self.snapshots = Some(config);
+        self
+    }
+
+    /// Get a handle that can stop the engine once it is running.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Get a handle that can control the engine and its components once it is running.
+    pub fn handle(&self) -> EngineHandle<E, A> {
+        self.handle.clone()
+    }
+
+    /// Serve the engine's metrics for Prometheus at `http://<address>/metrics`.
+    pub fn with_metrics_endpoint(mut self, address: SocketAddr) -> Self {
+        self.metrics_address = Some(address);
+        self
+    }
+
+    /// The engine's metrics, e.g. to expose them through an existing HTTP server with [`EngineMetrics::encode`].
+    pub fn metrics(&self) -> EngineMetrics {
+        self.metrics.clone()
+    }
+
     pub fn strategy_count(&self) -> usize {
         self.strategies.len()
     }
@@ -56,19 +204,86 @@ impl<E, A> Default for Engine<E, A> {
 
 impl<E, A> Engine<E, A>
 where
-    E: Send + Sync + Clone + 'static,
+    E: Send + Sync + Clone + Debug + 'static,
     A: Send + Sync + Clone + Debug + 'static,
 {
     pub fn add_collector(&mut self, collector: Box<dyn ICollector<E>>) {
-        self.collectors.push(collector);
+        self.collectors.push((collector, None));
+    }
+
+    pub fn add_collector_with_restart_policy(&mut self, collector: Box<dyn ICollector<E>>, policy: RestartPolicy) {
+        self.collectors.push((collector, Some(policy)));
+    }
+
+    /// Add a collector of one variant's payload, e.g. `engine.add_collector_for::<event::Block>(block_collector)`
+    /// with an event enum deriving [`HarpoonEvent`](crate::HarpoonEvent).
+    pub fn add_collector_for<V>(&mut self, collector: impl ICollector<V::Payload> + 'static)
+    where
+        V: IVariant<E> + 'static,
+        V::Payload: Send + Sync + 'static,
+    {
+        self.add_collector(Box::new(CollectorMap::new(Box::new(collector), V::wrap)));
     }
 
     pub fn add_strategy(&mut self, strategy: Box<dyn IStrategy<E, A>>) {
-        self.strategies.push(strategy);
+        self.strategies.push((strategy, None, None));
+    }
+
+    pub fn add_strategy_with_panic_policy(&mut self, strategy: Box<dyn IStrategy<E, A>>, policy: PanicPolicy) {
+        self.strategies.push((strategy, Some(policy), None));
+    }
+
+    /// Add a strategy of one event variant's payload, submitting the payload of one action variant, e.g.
+    /// `engine.add_strategy_for::<event::Log, action::Message>(alert)`. See [`StrategyMap`].
+    pub fn add_strategy_for<VE, VA>(&mut self, strategy: impl IStrategy<VE::Payload, VA::Payload> + 'static)
+    where
+        VE: IVariant<E> + 'static,
+        VA: IVariant<A> + 'static,
+        VE::Payload: Send + Sync + Clone + 'static,
+        VA::Payload: Send + Sync + Clone + 'static,
+    {
+        self.add_strategy(Box::new(StrategyMap::new(
+            Box::new(strategy),
+            VE::extract_ref,
+            VA::wrap,
+        )));
+    }
+
+    /// Run `shards` instances of a strategy, built by `factory` from their index, in parallel. Each event goes to
+    /// the instance owning its [`IStrategy::shard_key`], so events with the same key are processed in order while a
+    /// slow event only holds back its own shard. Events without a key go to every instance.
+    ///
+    /// Instances share the strategy's name, so the [`EngineHandle`] pauses and reports on them together.
+    pub fn add_sharded_strategy<F>(&mut self, shards: usize, mut factory: F)
+    where
+        F: FnMut(usize) -> Box<dyn IStrategy<E, A>>,
+    {
+        assert!(shards > 0, "a sharded strategy needs at least one shard");
+
+        for index in 0..shards {
+            let shard = Shard { index, count: shards };
+            self.strategies.push((factory(index), None, Some(shard)));
+        }
     }
 
     pub fn add_executor(&mut self, executor: Box<dyn IExecutor<A>>) {
-        self.executors.push(executor);
+        self.executors.push((executor, None));
+    }
+
+    pub fn add_executor_with_config(&mut self, executor: Box<dyn IExecutor<A>>, config: ExecutorConfig) {
+        self.executors.push((executor, Some(config)));
+    }
+
+    /// Add an executor of one variant's payload, e.g. `engine.add_executor_for::<action::SendTx>(sender)` with an
+    /// action enum deriving [`HarpoonAction`](crate::HarpoonAction). Other actions are skipped.
+    pub fn add_executor_for<V>(&mut self, executor: impl IExecutor<V::Payload> + 'static)
+    where
+        V: IVariant<A> + 'static,
+        V::Payload: Send + Sync + 'static,
+    {
+        self.add_executor(Box::new(
+            ExecutorMap::new(Box::new(executor), V::extract).with_ref(V::extract_ref),
+        ));
     }
 
     pub async fn run_and_join(self) -> Result<(), Box<dyn std::error::Error>> {
@@ -84,8 +299,8 @@ where
     }
 
     pub async fn run(self) -> Result<JoinSet<()>, Box<dyn std::error::Error>> {
-        let (event_sender, _): (Sender<E>, _) = broadcast::channel(self.event_channel_capacity);
-        let (action_sender, _): (Sender<A>, _) = broadcast::channel(self.action_channel_capacity);
+        let (event_sender, _): (Sender<Arc<Envelope<E>>>, _) = broadcast::channel(self.event_channel_capacity);
+        let (engine_event_sender, _): (Sender<EngineEvent>, _) = broadcast::channel(self.engine_event_channel_capacity);
 
         let mut set = JoinSet::new();
 
@@ -101,81 +316,190 @@ where
             return Err("no strategies".into());
         }
 
-        // Spawn collectors in separate threads.
-        for collector in self.collectors {
-            let event_sender = event_sender.clone();
+        let shutdown = self.shutdown;
+        let deadline = CancellationToken::new();
 
-            set.spawn(async move {
-                debug!(name = collector.name(), "starting collector... ");
-                let mut event_stream = collector.get_event_stream().await.unwrap();
+        if let Some(address) = self.metrics_address {
+            let listener = tokio::net::TcpListener::bind(address).await?;
+            tokio::spawn(metrics::serve(listener, self.metrics.clone(), shutdown.clone()));
+        }
+        let registry = self.handle.registry();
+
+        if self.shutdown_on_signal {
+            let shutdown = shutdown.clone();
 
-                while let Some(event) = event_stream.next().await {
-                    if let Err(e) = event_sender.send(event) {
-                        error!(name = collector.name(), "error sending event: {e:#}");
+            set.spawn(async move {
+                tokio::select! {
+                    _ = shutdown::wait_for_signal() => {
+                        info!("shutdown signal received");
+                        shutdown.shutdown();
                     }
+                    _ = shutdown.wait() => {}
                 }
+            });
+        }
 
-                error!(name = collector.name(), "event stream ended!");
+        // Strategies and executors are aborted once the shutdown timeout elapses.
+        {
+            let shutdown = shutdown.clone();
+            let deadline = deadline.clone();
+            let timeout = self.shutdown_timeout;
+
+            tokio::spawn(async move {
+                shutdown.wait().await;
+                tokio::time::sleep(timeout).await;
+                deadline.cancel();
             });
         }
 
-        // Spawn strategies in separate threads.
-        for mut strategy in self.strategies {
-            let mut event_receiver = event_sender.subscribe();
-            let action_sender = action_sender.clone();
+        // Counts down to a shutdown if every collector is finite, or, when backtesting, if any is.
+        let finite = self.collectors.iter().filter(|(c, _)| c.is_finite()).count();
+        let remaining_finite = if finite == self.collectors.len() || (self.clock.is_simulated() && finite > 0) {
+            finite
+        } else {
+            usize::MAX
+        };
+        let remaining_finite = Arc::new(AtomicUsize::new(remaining_finite));
+        let next_sequence = Arc::new(AtomicU64::new(0));
+        let events_consumed = Arc::new(Notify::new());
+
+        // Every executor gets its own queue so a slow executor can't hold back the others.
+        let mut queues = Vec::with_capacity(self.executors.len());
+
+        for (executor, config) in self.executors {
+            let config =
+                config.unwrap_or_else(|| ExecutorConfig::default().with_capacity(self.action_channel_capacity));
+            let queue = Arc::new(ActionQueue::new(executor.name(), config.capacity, config.overflow));
+            let component = registry.register(executor.name(), ComponentKind::Executor);
+            let metrics = self.metrics.executor(executor.name());
+
+            set.spawn(executor::run_executor(
+                Arc::from(executor),
+                component,
+                metrics.clone(),
+                config.panic_policy.unwrap_or(self.panic_policy),
+                queue.clone(),
+                config.concurrency,
+                ExecutorContext {
+                    clock: self.clock.clone(),
+                    engine_event_sender: engine_event_sender.clone(),
+                    deadline: deadline.clone(),
+                },
+            ));
+
+            queues.push((queue, metrics));
+        }
+
+        let action_router = Arc::new(ActionRouter::new(queues, self.clock.clone()));
 
-            let action_submitter = Arc::new(ActionChannelSubmitter::new(action_sender));
+        // Spawn strategies in separate threads.
+        for (mut strategy, panic_policy, shard) in self.strategies {
+            let engine_event_receiver = engine_event_sender.subscribe();
+
+            let (report_sender, report_receiver) = mpsc::unbounded_channel();
+            let action_submitter: Arc<dyn IActionSubmitter<A>> =
+                Arc::new(StrategySubmitter::new(action_router.clone(), report_sender));
+
+            let snapshots = match &self.snapshots {
+                Some(config) => {
+                    let snapshots = config.for_strategy(strategy.name(), shard, self.clock.clone());
+                    config.restore(&mut strategy, &snapshots, shard).await?;
+                    Some(snapshots)
+                }
+                None => None,
+            };
 
             strategy
                 .sync_state(action_submitter.clone())
                 .await
                 .wrap_err("fail to sync state")?;
 
-            set.spawn(async move {
-                debug!(name = strategy.name(), "starting strategy...");
-
-                loop {
-                    match event_receiver.recv().await {
-                        Ok(event) => strategy.process_event(event, action_submitter.clone()).await,
-                        Err(RecvError::Closed) => {
-                            error!(name = strategy.name(), "event channel closed!");
-                            break;
-                        }
-                        Err(RecvError::Lagged(num)) => {
-                            warn!(name = strategy.name(), "event channel lagged by {num}")
-                        }
-                    }
-                }
-            });
+            let event_receiver = event_sender.subscribe();
+
+            let component = registry.register(strategy.name(), ComponentKind::Strategy);
+            let metrics = self.metrics.strategy(strategy.name());
+
+            set.spawn(strategy::run_strategy(
+                strategy,
+                component,
+                metrics,
+                panic_policy.unwrap_or(self.panic_policy),
+                action_submitter,
+                StrategyInputs {
+                    events: event_receiver,
+                    actions: action_router.clone(),
+                    shard,
+                    engine_events: engine_event_receiver,
+                    reports: report_receiver,
+                    snapshots,
+                },
+                StrategyContext {
+                    engine_event_sender: engine_event_sender.clone(),
+                    events_consumed: events_consumed.clone(),
+                    shutdown: shutdown.clone(),
+                    deadline: deadline.clone(),
+                },
+            ));
         }
 
-        // Spawn executors in separate threads.
-        for executor in self.executors {
-            let mut receiver = action_sender.subscribe();
+        // Spawn collectors in separate threads, once every strategy is restored and subscribed, so that live events
+        // neither pile up in the event channel during a replay nor end up in the recording being replayed.
+        for (collector, policy) in self.collectors {
+            let policy = policy.unwrap_or_else(|| self.restart_policy.clone());
+
+            debug!(name = collector.name(), "starting collector... ");
+
+            let component = registry.register(collector.name(), ComponentKind::Collector);
+            let metrics = self.metrics.collector(collector.name());
+
+            set.spawn(supervisor::run_collector(
+                collector,
+                component,
+                metrics,
+                policy,
+                CollectorContext {
+                    event_sender: event_sender.clone(),
+                    event_channel_capacity: self.event_channel_capacity,
+                    next_sequence: next_sequence.clone(),
+                    clock: self.clock.clone(),
+                    engine_event_sender: engine_event_sender.clone(),
+                    remaining_finite: remaining_finite.clone(),
+                    events_consumed: events_consumed.clone(),
+                    shutdown: shutdown.clone(),
+                },
+            ));
+        }
+
+        // Collectors and strategies added through the handle are joined here. The action router is held until the
+        // engine shuts down, so executors keep running even if every strategy is removed.
+        let tasks = Arc::new(AddedTasks::new());
+        {
+            let tasks = tasks.clone();
+            let shutdown = shutdown.clone();
+            let action_router = action_router.clone();
 
             set.spawn(async move {
-                debug!(name = executor.name(), "starting executor... ");
-
-                loop {
-                    match receiver.recv().await {
-                        Ok(action) => match executor.execute(action).await {
-                            Ok(_) => {}
-                            Err(e) => {
-                                error!(name = executor.name(), "error executing action: {}", e)
-                            }
-                        },
-                        Err(RecvError::Closed) => {
-                            error!(name = executor.name(), "action channel closed!");
-                            break;
-                        }
-                        Err(RecvError::Lagged(num)) => {
-                            warn!(name = executor.name(), "action channel lagged by {num}")
-                        }
-                    }
-                }
+                tasks.join(&shutdown).await;
+                drop(action_router);
             });
         }
 
+        self.handle.attach(Runtime {
+            event_sender: event_sender.downgrade(),
+            event_channel_capacity: self.event_channel_capacity,
+            next_sequence,
+            events_consumed,
+            clock: self.clock.clone(),
+            engine_event_sender: engine_event_sender.downgrade(),
+            action_router: Arc::downgrade(&action_router),
+            tasks,
+            restart_policy: self.restart_policy,
+            panic_policy: self.panic_policy,
+            metrics: self.metrics,
+            shutdown,
+            deadline,
+        });
+
         Ok(set)
     }
 }
@@ -0,0 +1,51 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::CorrelationId;
+
+/// An event along with what the engine knows about where and when it came from, delivered to
+/// [`IStrategy::process_envelope`](crate::IStrategy::process_envelope).
+#[derive(Debug, Clone)]
+pub struct Envelope<E> {
+    pub meta: EventMeta,
+    pub event: E,
+}
+
+impl<E> Envelope<E> {
+    pub fn map<T>(self, f: impl FnOnce(E) -> T) -> Envelope<T> {
+        Envelope {
+            meta: self.meta,
+            event: f(self.event),
+        }
+    }
+}
+
+/// Attached by the engine to every event as it leaves its collector.
+#[derive(Debug, Clone)]
+pub struct EventMeta {
+    /// [Name](crate::ICollector::name) of the collector that emitted the event.
+    pub collector: Arc<str>,
+    /// Chain the collector follows, if it [knows](crate::ICollector::chain_id).
+    pub chain_id: Option<u64>,
+    /// When the engine received the event.
+    pub received_at: Instant,
+    /// Wall clock time at `received_at`, according to the engine's [`Clock`](crate::Clock); simulated when
+    /// backtesting.
+    pub received_time: SystemTime,
+    /// Position among every event the engine received, starting at 0.
+    pub sequence: u64,
+}
+
+impl EventMeta {
+    pub fn correlation_id(&self) -> CorrelationId {
+        CorrelationId(self.sequence)
+    }
+
+    /// Time since the engine received the event, e.g. to skip a mempool transaction that waited too long behind
+    /// others.
+    pub fn age(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+}
@@ -0,0 +1,35 @@
+use std::time::Duration;
+
+/// Notifications about the engine itself, delivered to every strategy through
+/// [`IStrategy::on_engine_event`](crate::IStrategy::on_engine_event).
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum EngineEvent {
+    /// A collector's event stream came back after failing or ending. Events produced while it was down may have
+    /// been missed.
+    CollectorRestarted {
+        collector: String,
+        attempt: u32,
+        downtime: Duration,
+    },
+
+    /// A collector ran out of restart attempts and will not produce any more events.
+    CollectorStopped { collector: String, attempts: u32 },
+
+    /// A strategy panicked while handling `input`, the Debug-formatted event, engine event or execution report.
+    /// It is either restarted or quarantined according to its [`PanicPolicy`](super::PanicPolicy).
+    StrategyPanicked {
+        strategy: String,
+        input: String,
+        panic: String,
+        quarantined: bool,
+    },
+
+    /// An executor panicked while executing `action`, which is reported as failed to the strategy that submitted it.
+    ExecutorPanicked {
+        executor: String,
+        action: String,
+        panic: String,
+        quarantined: bool,
+    },
+}
@@ -0,0 +1,280 @@
+use std::{fmt::Debug, sync::Arc, time::Instant};
+
+use tokio::{
+    sync::{Semaphore, broadcast::Sender},
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, error, info, info_span, warn};
+
+use super::{
+    EngineEvent, ExecutionReport, PanicPolicy,
+    handle::{Component, ComponentState},
+    metrics::ExecutorMetrics,
+    panic::catch_panic,
+    queue::{ActionQueue, OverflowPolicy, QueuedAction},
+    trace::ActionOrigin,
+};
+use crate::{Clock, ExecutionOutcome, IExecutor};
+
+/// Queueing, concurrency and panic settings of a single executor.
+#[derive(Debug, Clone)]
+pub struct ExecutorConfig {
+    /// Maximum number of actions waiting in the executor's queue.
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+    /// Maximum number of `execute` calls running at the same time. With `1`, actions are executed in submission
+    /// order.
+    pub concurrency: usize,
+    /// `None` uses the engine's panic policy.
+    pub panic_policy: Option<PanicPolicy>,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 512,
+            overflow: OverflowPolicy::default(),
+            concurrency: 1,
+            panic_policy: None,
+        }
+    }
+}
+
+impl ExecutorConfig {
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    pub fn with_panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = Some(policy);
+        self
+    }
+}
+
+/// What every executor task shares with the rest of the engine.
+#[derive(Clone)]
+pub(crate) struct ExecutorContext {
+    pub(crate) clock: Clock,
+    pub(crate) engine_event_sender: Sender<EngineEvent>,
+    pub(crate) deadline: CancellationToken,
+}
+
+/// Execute actions from `queue` until it is closed and drained, or until `deadline` is cancelled. While the executor
+/// is paused, actions stay in the queue; once it is quarantined after a panic, they fail right away, except those it
+/// isn't [interested](IExecutor::is_interested) in, which are skipped either way.
+pub(crate) async fn run_executor<A>(
+    executor: Arc<dyn IExecutor<A>>,
+    component: Arc<Component>,
+    metrics: ExecutorMetrics,
+    panic_policy: PanicPolicy,
+    queue: Arc<ActionQueue<QueuedAction<A>>>,
+    concurrency: usize,
+    context: ExecutorContext,
+) where
+    A: Send + Sync + Clone + Debug + 'static,
+{
+    let ExecutorContext {
+        clock,
+        engine_event_sender,
+        deadline,
+    } = context;
+
+    let name = executor.name().to_string();
+    debug!(name, "starting executor... ");
+
+    let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut in_flight = JoinSet::new();
+
+    loop {
+        let permit = tokio::select! {
+            _ = deadline.cancelled() => break,
+            permit = permits.clone().acquire_owned() => permit.expect("semaphore is never closed"),
+        };
+
+        let action = tokio::select! {
+            _ = deadline.cancelled() => break,
+            action = queue.pop() => action,
+        };
+
+        let Some(QueuedAction {
+            id,
+            action,
+            reports,
+            trace,
+        }) = action
+        else {
+            break;
+        };
+
+        if !executor.is_interested(&action) {
+            continue;
+        }
+
+        let correlation_id = trace.as_ref().map(|trace| trace.origin.event.correlation_id());
+
+        if component.is_quarantined() {
+            debug!(name, action_id = %id, "executor quarantined, failing action");
+            metrics.actions_failed.inc();
+
+            let _ = reports.send(ExecutionReport {
+                action_id: id,
+                correlation_id,
+                executor: name.clone(),
+                outcome: ExecutionOutcome::Failed {
+                    error: "executor quarantined".to_string(),
+                    tx_hash: None,
+                },
+                started_at: clock.now(),
+                elapsed: Default::default(),
+            });
+
+            continue;
+        }
+
+        if component.is_paused() {
+            info!(name, "executor paused");
+
+            tokio::select! {
+                _ = deadline.cancelled() => break,
+                _ = component.resumed() => info!(name, "executor resumed"),
+            }
+        }
+
+        let task_executor = executor.clone();
+        let task_component = component.clone();
+        let task_metrics = metrics.clone();
+        let task_engine_event_sender = engine_event_sender.clone();
+        let started_at = clock.now();
+
+        // Nested under the span the action was submitted in, so the execution is traced back to its event.
+        let span = match &trace {
+            Some(trace) => info_span!(
+                parent: &trace.span,
+                "execute",
+                executor = name,
+                action_id = %id,
+                correlation_id = %trace.origin.event.correlation_id(),
+            ),
+            None => info_span!("execute", executor = name, action_id = %id),
+        };
+
+        let task = async move {
+            let _permit = permit;
+            let name = task_executor.name();
+            let input = action.clone();
+            let origin = trace.as_ref().map(|trace| trace.origin.clone());
+
+            let start = Instant::now();
+            let result = catch_panic(ActionOrigin::scope(origin, task_executor.execute_with_outcome(action))).await;
+            let elapsed = start.elapsed();
+
+            if let Some(trace) = &trace {
+                let hops = trace.hops(start);
+                debug!(
+                    name,
+                    action_id = %id,
+                    correlation_id = %trace.origin.event.correlation_id(),
+                    collector = %trace.origin.event.collector,
+                    strategy = %trace.origin.strategy,
+                    delivery = ?hops.delivery,
+                    strategy_time = ?hops.strategy,
+                    queued = ?hops.queued,
+                    execute = ?elapsed,
+                    "action handled"
+                );
+            }
+
+            let outcome = match result {
+                Ok(outcome) => outcome,
+                Err(panic) => {
+                    let action = format!("{input:?}");
+                    error!(name, action_id = %id, action, "executor panicked: {panic}");
+
+                    let quarantined = panic_policy == PanicPolicy::Quarantine;
+                    if quarantined {
+                        warn!(name, "executor quarantined");
+                        task_component.set_state(ComponentState::Quarantined);
+                    }
+
+                    let _ = task_engine_event_sender.send(EngineEvent::ExecutorPanicked {
+                        executor: name.to_string(),
+                        action,
+                        panic: panic.clone(),
+                        quarantined,
+                    });
+
+                    ExecutionOutcome::Failed {
+                        error: format!("executor panicked: {panic}"),
+                        tx_hash: None,
+                    }
+                }
+            };
+
+            match &outcome {
+                ExecutionOutcome::Skipped => return,
+                ExecutionOutcome::Failed { error, .. } => {
+                    error!(name, action_id = %id, "error executing action: {error}");
+                    task_metrics.actions_failed.inc();
+                }
+                _ => {
+                    task_metrics.actions_executed.inc();
+                }
+            }
+
+            task_metrics.observe_execute(elapsed);
+            task_component.record_processed();
+
+            // The strategy may have stopped already during shutdown.
+            let _ = reports.send(ExecutionReport {
+                action_id: id,
+                correlation_id,
+                executor: name.to_string(),
+                outcome,
+                started_at,
+                elapsed,
+            });
+        };
+
+        in_flight.spawn(task.instrument(span));
+
+        while let Some(result) = in_flight.try_join_next() {
+            if let Err(e) = result {
+                error!(name, "execution task terminated unexpectedly: {e:#}");
+            }
+        }
+    }
+
+    tokio::select! {
+        _ = deadline.cancelled() => {
+            if !in_flight.is_empty() {
+                warn!(name, "{} in-flight actions aborted after shutdown timeout", in_flight.len());
+            }
+            in_flight.abort_all();
+        }
+        _ = async {
+            while let Some(result) = in_flight.join_next().await {
+                if let Err(e) = result {
+                    error!(name, "execution task terminated unexpectedly: {e:#}");
+                }
+            }
+        } => {
+            info!(name, "executor stopped");
+        }
+    }
+
+    if !component.is_quarantined() {
+        component.set_state(ComponentState::Stopped);
+    }
+}
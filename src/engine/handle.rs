@@ -0,0 +1,454 @@
+use std::{
+    fmt::Debug,
+    future::{Future, poll_fn},
+    sync::{
+        Arc, Mutex, OnceLock, Weak,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    task::Poll,
+};
+
+use eyre::{Context, bail, eyre};
+use tokio::{
+    sync::{
+        Notify,
+        broadcast::{self, WeakSender},
+        mpsc, watch,
+    },
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+use super::{
+    EngineEvent, EngineMetrics, Envelope, PanicPolicy, RestartPolicy, ShutdownHandle,
+    queue::{ActionRouter, StrategySubmitter},
+    strategy::{self, StrategyContext, StrategyInputs},
+    supervisor::{self, CollectorContext},
+};
+use crate::{Clock, IActionSubmitter, ICollector, IStrategy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    Collector,
+    Strategy,
+    Executor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentState {
+    Running,
+    /// Paused through [`EngineHandle::pause`].
+    Paused,
+    /// A collector waiting to re-subscribe after its stream failed or ended.
+    Restarting,
+    /// A strategy or executor stopped after panicking; see [`PanicPolicy`](super::PanicPolicy).
+    Quarantined,
+    Stopped,
+}
+
+#[derive(Debug, Clone)]
+pub struct ComponentStatus {
+    pub name: String,
+    pub kind: ComponentKind,
+    pub state: ComponentState,
+    /// Events emitted by a collector, events processed by a strategy, or actions executed by an executor.
+    pub processed: u64,
+}
+
+/// Runtime bookkeeping of a single collector, strategy or executor.
+pub(crate) struct Component {
+    name: String,
+    kind: ComponentKind,
+    state: Mutex<ComponentState>,
+    processed: AtomicU64,
+    paused: watch::Sender<bool>,
+    removed: CancellationToken,
+}
+
+impl Component {
+    pub(crate) fn set_state(&self, state: ComponentState) {
+        *self.state.lock().unwrap() = state;
+    }
+
+    pub(crate) fn record_processed(&self) {
+        self.processed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_quarantined(&self) -> bool {
+        *self.state.lock().unwrap() == ComponentState::Quarantined
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Wait until the component is not paused.
+    pub(crate) async fn resumed(&self) {
+        let _ = self.paused.subscribe().wait_for(|paused| !paused).await;
+    }
+
+    /// Wait until the component has been removed through [`EngineHandle::remove`].
+    pub(crate) async fn removed(&self) {
+        self.removed.cancelled().await
+    }
+
+    fn status(&self) -> ComponentStatus {
+        let state = match *self.state.lock().unwrap() {
+            ComponentState::Running if self.is_paused() => ComponentState::Paused,
+            state => state,
+        };
+
+        ComponentStatus {
+            name: self.name.clone(),
+            kind: self.kind,
+            state,
+            processed: self.processed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Registry {
+    components: Mutex<Vec<Arc<Component>>>,
+}
+
+impl Registry {
+    pub(crate) fn register(&self, name: impl Into<String>, kind: ComponentKind) -> Arc<Component> {
+        let component = Arc::new(Component {
+            name: name.into(),
+            kind,
+            state: Mutex::new(ComponentState::Running),
+            processed: AtomicU64::new(0),
+            paused: watch::Sender::new(false),
+            removed: CancellationToken::new(),
+        });
+
+        self.components.lock().unwrap().push(component.clone());
+        component
+    }
+
+    fn find(&self, name: &str, kinds: &[ComponentKind]) -> Vec<Arc<Component>> {
+        self.components
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|c| c.name == name && kinds.contains(&c.kind))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Tasks of the collectors and strategies added while the engine runs. A task in the engine's own set joins them,
+/// so the engine doesn't finish before they do and their panics are reported.
+pub(crate) struct AddedTasks {
+    /// `None` once the engine has shut down and every added task has finished.
+    tasks: Mutex<Option<JoinSet<()>>>,
+    spawned: Notify,
+}
+
+impl AddedTasks {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Mutex::new(Some(JoinSet::new())),
+            spawned: Notify::new(),
+        }
+    }
+
+    fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) -> eyre::Result<()> {
+        let mut tasks = self.tasks.lock().unwrap();
+        let Some(tasks) = tasks.as_mut() else {
+            bail!("engine is shutting down");
+        };
+
+        tasks.spawn(task);
+        self.spawned.notify_one();
+        Ok(())
+    }
+
+    /// Join added tasks until a shutdown has been requested and every one of them has finished.
+    pub(crate) async fn join(&self, shutdown: &ShutdownHandle) {
+        loop {
+            let joined = poll_fn(|cx| match self.tasks.lock().unwrap().as_mut() {
+                Some(tasks) => tasks.poll_join_next(cx),
+                None => Poll::Ready(None),
+            })
+            .await;
+
+            match joined {
+                Some(Ok(())) => {}
+                Some(Err(err)) => error!("task terminated unexpectedly: {err:#}"),
+                None => tokio::select! {
+                    _ = self.spawned.notified() => {}
+                    _ = shutdown.wait() => {
+                        let mut tasks = self.tasks.lock().unwrap();
+                        if tasks.as_ref().is_none_or(JoinSet::is_empty) {
+                            *tasks = None;
+                            return;
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// What the handle needs from a running engine to add components to it. Channels and the action router are held
+/// weakly, so the handle doesn't keep the engine from shutting down; the engine keeps the action router alive until
+/// it has shut down.
+pub(crate) struct Runtime<E, A> {
+    pub(crate) event_sender: WeakSender<Arc<Envelope<E>>>,
+    pub(crate) event_channel_capacity: usize,
+    pub(crate) next_sequence: Arc<AtomicU64>,
+    pub(crate) events_consumed: Arc<Notify>,
+    pub(crate) clock: Clock,
+    pub(crate) engine_event_sender: WeakSender<EngineEvent>,
+    pub(crate) action_router: Weak<ActionRouter<A>>,
+    pub(crate) tasks: Arc<AddedTasks>,
+    pub(crate) restart_policy: RestartPolicy,
+    pub(crate) panic_policy: PanicPolicy,
+    pub(crate) metrics: EngineMetrics,
+    pub(crate) shutdown: ShutdownHandle,
+    pub(crate) deadline: CancellationToken,
+}
+
+/// A cloneable handle to control an [`Engine`](crate::Engine) while it runs: pause and resume strategies and
+/// executors, add and remove collectors and strategies, and query the status of every component.
+///
+/// Components are addressed by [`name`](crate::IStrategy::name); an operation applies to every component of the
+/// matching kinds with that name. Get a handle with [`Engine::handle`](crate::Engine::handle) before running the
+/// engine; adding components fails until the engine runs.
+pub struct EngineHandle<E, A> {
+    registry: Arc<Registry>,
+    runtime: Arc<OnceLock<Runtime<E, A>>>,
+}
+
+impl<E, A> Clone for EngineHandle<E, A> {
+    fn clone(&self) -> Self {
+        Self {
+            registry: self.registry.clone(),
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+impl<E, A> EngineHandle<E, A> {
+    pub(crate) fn new() -> Self {
+        Self {
+            registry: Arc::new(Registry::default()),
+            runtime: Arc::new(OnceLock::new()),
+        }
+    }
+
+    pub(crate) fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub(crate) fn attach(&self, runtime: Runtime<E, A>) {
+        if self.runtime.set(runtime).is_err() {
+            unreachable!("an engine runs only once");
+        }
+    }
+
+    fn runtime(&self) -> eyre::Result<&Runtime<E, A>> {
+        let runtime = self.runtime.get().ok_or_else(|| eyre!("engine is not running"))?;
+
+        if runtime.shutdown.is_shutdown() {
+            bail!("engine is shutting down");
+        }
+
+        Ok(runtime)
+    }
+
+    /// Status of every collector, strategy and executor, in the order they were added.
+    pub fn status(&self) -> Vec<ComponentStatus> {
+        self.registry
+            .components
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|c| c.status())
+            .collect()
+    }
+
+    /// Status of the components named `name`.
+    pub fn status_of(&self, name: &str) -> Vec<ComponentStatus> {
+        self.status().into_iter().filter(|s| s.name == name).collect()
+    }
+
+    /// Pause the strategies and executors named `name`. A paused strategy skips the events it receives; a paused
+    /// executor leaves submitted actions in its queue, subject to its [`OverflowPolicy`](super::OverflowPolicy).
+    pub fn pause(&self, name: &str) -> eyre::Result<()> {
+        self.set_paused(name, true)
+    }
+
+    /// Resume the strategies and executors named `name`.
+    pub fn resume(&self, name: &str) -> eyre::Result<()> {
+        self.set_paused(name, false)
+    }
+
+    fn set_paused(&self, name: &str, paused: bool) -> eyre::Result<()> {
+        let components = self
+            .registry
+            .find(name, &[ComponentKind::Strategy, ComponentKind::Executor]);
+
+        if components.is_empty() {
+            bail!("no strategy or executor named `{name}`");
+        }
+
+        for component in components {
+            component.paused.send_replace(paused);
+        }
+
+        info!(name, paused, "component pause state changed");
+        Ok(())
+    }
+
+    /// Stop the collectors and strategies named `name` and forget about them. The last running collector can't be
+    /// removed, since strategies would lose their event channel.
+    pub fn remove(&self, name: &str) -> eyre::Result<()> {
+        let components = self
+            .registry
+            .find(name, &[ComponentKind::Collector, ComponentKind::Strategy]);
+
+        if components.is_empty() {
+            bail!("no collector or strategy named `{name}`");
+        }
+
+        let mut registered = self.registry.components.lock().unwrap();
+
+        let running_collector = |c: &Arc<Component>| {
+            c.kind == ComponentKind::Collector && *c.state.lock().unwrap() != ComponentState::Stopped
+        };
+        let remaining = registered
+            .iter()
+            .filter(|c| c.name != name && running_collector(c))
+            .count();
+
+        if remaining == 0 && components.iter().any(running_collector) {
+            bail!("can't remove the last running collector");
+        }
+
+        for component in &components {
+            component.removed.cancel();
+        }
+        registered.retain(|c| !components.iter().any(|removed| Arc::ptr_eq(c, removed)));
+
+        info!(name, "component removed");
+        Ok(())
+    }
+}
+
+impl<E, A> EngineHandle<E, A>
+where
+    E: Send + Sync + Clone + Debug + 'static,
+    A: Send + Sync + Clone + 'static,
+{
+    /// Start `collector` in the running engine, restarted according to the engine's restart policy.
+    pub fn add_collector(&self, collector: Box<dyn ICollector<E>>) -> eyre::Result<()> {
+        let policy = self.runtime()?.restart_policy.clone();
+        self.add_collector_with_restart_policy(collector, policy)
+    }
+
+    pub fn add_collector_with_restart_policy(
+        &self,
+        collector: Box<dyn ICollector<E>>,
+        policy: RestartPolicy,
+    ) -> eyre::Result<()> {
+        let runtime = self.runtime()?;
+
+        let event_sender = runtime
+            .event_sender
+            .upgrade()
+            .ok_or_else(|| eyre!("event channel closed"))?;
+        let engine_event_sender = runtime
+            .engine_event_sender
+            .upgrade()
+            .unwrap_or_else(|| broadcast::channel(1).0);
+
+        debug!(name = collector.name(), "adding collector... ");
+
+        let component = self.registry.register(collector.name(), ComponentKind::Collector);
+        let metrics = runtime.metrics.collector(collector.name());
+
+        // Added collectors never count towards stopping the engine once finite collectors are done.
+        runtime.tasks.spawn(supervisor::run_collector(
+            collector,
+            component,
+            metrics,
+            policy,
+            CollectorContext {
+                event_sender,
+                event_channel_capacity: runtime.event_channel_capacity,
+                next_sequence: runtime.next_sequence.clone(),
+                clock: runtime.clock.clone(),
+                engine_event_sender,
+                remaining_finite: Arc::new(AtomicUsize::new(usize::MAX)),
+                events_consumed: runtime.events_consumed.clone(),
+                shutdown: runtime.shutdown.clone(),
+            },
+        ))
+    }
+
+    /// Sync `strategy` and start feeding it events from the running engine, under the engine's panic policy.
+    pub async fn add_strategy(&self, strategy: Box<dyn IStrategy<E, A>>) -> eyre::Result<()> {
+        let policy = self.runtime()?.panic_policy;
+        self.add_strategy_with_panic_policy(strategy, policy).await
+    }
+
+    pub async fn add_strategy_with_panic_policy(
+        &self,
+        mut strategy: Box<dyn IStrategy<E, A>>,
+        policy: PanicPolicy,
+    ) -> eyre::Result<()> {
+        let runtime = self.runtime()?;
+
+        let event_receiver = runtime
+            .event_sender
+            .upgrade()
+            .ok_or_else(|| eyre!("event channel closed"))?
+            .subscribe();
+        let engine_event_sender = runtime
+            .engine_event_sender
+            .upgrade()
+            .unwrap_or_else(|| broadcast::channel(1).0);
+        let action_router = runtime
+            .action_router
+            .upgrade()
+            .ok_or_else(|| eyre!("executors stopped"))?;
+
+        let (report_sender, report_receiver) = mpsc::unbounded_channel();
+        let action_submitter: Arc<dyn IActionSubmitter<A>> =
+            Arc::new(StrategySubmitter::new(action_router.clone(), report_sender));
+
+        strategy
+            .sync_state(action_submitter.clone())
+            .await
+            .wrap_err("fail to sync state")?;
+
+        let component = self.registry.register(strategy.name(), ComponentKind::Strategy);
+        let metrics = runtime.metrics.strategy(strategy.name());
+
+        runtime.tasks.spawn(strategy::run_strategy(
+            strategy,
+            component,
+            metrics,
+            policy,
+            action_submitter,
+            StrategyInputs {
+                events: event_receiver,
+                actions: action_router,
+                shard: None,
+                engine_events: engine_event_sender.subscribe(),
+                reports: report_receiver,
+                snapshots: None,
+            },
+            StrategyContext {
+                engine_event_sender,
+                events_consumed: runtime.events_consumed.clone(),
+                shutdown: runtime.shutdown.clone(),
+                deadline: runtime.deadline.clone(),
+            },
+        ))
+    }
+}
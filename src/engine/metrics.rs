@@ -0,0 +1,279 @@
+use std::{sync::Arc, time::Duration};
+
+use prometheus_client::{
+    encoding::{EncodeLabelSet, text::encode},
+    metrics::{
+        counter::Counter,
+        family::Family,
+        histogram::{Histogram, exponential_buckets},
+    },
+    registry::Registry,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{debug, error, info};
+
+use super::ShutdownHandle;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct CollectorLabels {
+    collector: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct StrategyLabels {
+    strategy: String,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ExecutorLabels {
+    executor: String,
+}
+
+type HistogramFamily<S> = Family<S, Histogram, fn() -> Histogram>;
+
+fn latency_histogram() -> Histogram {
+    // 100µs up to ~13s.
+    Histogram::new(exponential_buckets(0.0001, 2.0, 18))
+}
+
+/// Prometheus metrics of an [`Engine`](crate::Engine), labelled by component name:
+///
+/// - `harpoon_collector_events_total`: events emitted per collector.
+/// - `harpoon_strategy_events_processed_total`, `harpoon_strategy_events_lagged_total`: events processed per
+///   strategy, and events it missed because the event channel lagged.
+/// - `harpoon_strategy_process_event_seconds`: `process_event` latency.
+/// - `harpoon_executor_actions_submitted_total`, `harpoon_executor_actions_executed_total`,
+///   `harpoon_executor_actions_failed_total`: actions routed to, executed by and failed in each executor.
+/// - `harpoon_executor_execute_seconds`: `execute` latency.
+#[derive(Clone)]
+pub struct EngineMetrics {
+    registry: Arc<Registry>,
+
+    collector_events: Family<CollectorLabels, Counter>,
+
+    strategy_events_processed: Family<StrategyLabels, Counter>,
+    strategy_events_lagged: Family<StrategyLabels, Counter>,
+    strategy_process_event_seconds: HistogramFamily<StrategyLabels>,
+
+    executor_actions_submitted: Family<ExecutorLabels, Counter>,
+    executor_actions_executed: Family<ExecutorLabels, Counter>,
+    executor_actions_failed: Family<ExecutorLabels, Counter>,
+    executor_execute_seconds: HistogramFamily<ExecutorLabels>,
+}
+
+impl EngineMetrics {
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("harpoon");
+
+        let collector_events = Family::<CollectorLabels, Counter>::default();
+        registry.register(
+            "collector_events",
+            "Events emitted by a collector",
+            collector_events.clone(),
+        );
+
+        let strategy_events_processed = Family::<StrategyLabels, Counter>::default();
+        registry.register(
+            "strategy_events_processed",
+            "Events processed by a strategy",
+            strategy_events_processed.clone(),
+        );
+
+        let strategy_events_lagged = Family::<StrategyLabels, Counter>::default();
+        registry.register(
+            "strategy_events_lagged",
+            "Events a strategy missed because the event channel lagged",
+            strategy_events_lagged.clone(),
+        );
+
+        let strategy_process_event_seconds: HistogramFamily<StrategyLabels> =
+            Family::new_with_constructor(latency_histogram);
+        registry.register(
+            "strategy_process_event_seconds",
+            "Time a strategy spent processing an event",
+            strategy_process_event_seconds.clone(),
+        );
+
+        let executor_actions_submitted = Family::<ExecutorLabels, Counter>::default();
+        registry.register(
+            "executor_actions_submitted",
+            "Actions routed to an executor",
+            executor_actions_submitted.clone(),
+        );
+
+        let executor_actions_executed = Family::<ExecutorLabels, Counter>::default();
+        registry.register(
+            "executor_actions_executed",
+            "Actions an executor handled without failing",
+            executor_actions_executed.clone(),
+        );
+
+        let executor_actions_failed = Family::<ExecutorLabels, Counter>::default();
+        registry.register(
+            "executor_actions_failed",
+            "Actions an executor failed to execute",
+            executor_actions_failed.clone(),
+        );
+
+        let executor_execute_seconds: HistogramFamily<ExecutorLabels> = Family::new_with_constructor(latency_histogram);
+        registry.register(
+            "executor_execute_seconds",
+            "Time an executor spent executing an action",
+            executor_execute_seconds.clone(),
+        );
+
+        Self {
+            registry: Arc::new(registry),
+            collector_events,
+            strategy_events_processed,
+            strategy_events_lagged,
+            strategy_process_event_seconds,
+            executor_actions_submitted,
+            executor_actions_executed,
+            executor_actions_failed,
+            executor_execute_seconds,
+        }
+    }
+
+    /// Render every metric in the OpenMetrics text format.
+    pub fn encode(&self) -> String {
+        let mut buffer = String::new();
+        encode(&mut buffer, &self.registry).expect("writing to a string never fails");
+        buffer
+    }
+
+    pub(crate) fn collector(&self, name: &str) -> CollectorMetrics {
+        let labels = CollectorLabels {
+            collector: name.to_string(),
+        };
+
+        CollectorMetrics {
+            events: self.collector_events.get_or_create_owned(&labels),
+        }
+    }
+
+    pub(crate) fn strategy(&self, name: &str) -> StrategyMetrics {
+        let labels = StrategyLabels {
+            strategy: name.to_string(),
+        };
+
+        StrategyMetrics {
+            events_processed: self.strategy_events_processed.get_or_create_owned(&labels),
+            events_lagged: self.strategy_events_lagged.get_or_create_owned(&labels),
+            process_event_seconds: self.strategy_process_event_seconds.get_or_create_owned(&labels),
+        }
+    }
+
+    pub(crate) fn executor(&self, name: &str) -> ExecutorMetrics {
+        let labels = ExecutorLabels {
+            executor: name.to_string(),
+        };
+
+        ExecutorMetrics {
+            actions_submitted: self.executor_actions_submitted.get_or_create_owned(&labels),
+            actions_executed: self.executor_actions_executed.get_or_create_owned(&labels),
+            actions_failed: self.executor_actions_failed.get_or_create_owned(&labels),
+            execute_seconds: self.executor_execute_seconds.get_or_create_owned(&labels),
+        }
+    }
+}
+
+impl Default for EngineMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Metrics of a single collector, resolved once so recording doesn't look up labels.
+#[derive(Clone)]
+pub(crate) struct CollectorMetrics {
+    pub(crate) events: Counter,
+}
+
+#[derive(Clone)]
+pub(crate) struct StrategyMetrics {
+    pub(crate) events_processed: Counter,
+    pub(crate) events_lagged: Counter,
+    process_event_seconds: Histogram,
+}
+
+impl StrategyMetrics {
+    pub(crate) fn observe_process_event(&self, elapsed: Duration) {
+        self.process_event_seconds.observe(elapsed.as_secs_f64());
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ExecutorMetrics {
+    pub(crate) actions_submitted: Counter,
+    pub(crate) actions_executed: Counter,
+    pub(crate) actions_failed: Counter,
+    execute_seconds: Histogram,
+}
+
+impl ExecutorMetrics {
+    pub(crate) fn observe_execute(&self, elapsed: Duration) {
+        self.execute_seconds.observe(elapsed.as_secs_f64());
+    }
+}
+
+/// Serve `GET /metrics` on `listener` until a shutdown is requested.
+pub(crate) async fn serve(listener: TcpListener, metrics: EngineMetrics, shutdown: ShutdownHandle) {
+    info!(address = ?listener.local_addr().ok(), "serving metrics");
+
+    loop {
+        let (stream, peer) = tokio::select! {
+            _ = shutdown.wait() => break,
+            accepted = listener.accept() => match accepted {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("fail to accept metrics connection: {e:#}");
+                    continue;
+                }
+            },
+        };
+
+        let metrics = metrics.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = respond(stream, &metrics).await {
+                debug!(?peer, "fail to serve metrics: {e:#}");
+            }
+        });
+    }
+}
+
+async fn respond(mut stream: TcpStream, metrics: &EngineMetrics) -> std::io::Result<()> {
+    let mut request = Vec::with_capacity(1024);
+    let mut buffer = [0; 1024];
+
+    // Only the request line matters; read until the end of the headers.
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < 8192 {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+    }
+
+    let request_line = request.split(|&b| b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|&b| b == b' ');
+
+    let response = match (parts.next(), parts.next()) {
+        (Some(b"GET"), Some(b"/metrics")) => {
+            let body = metrics.encode();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/openmetrics-text; version=1.0.0; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
@@ -0,0 +1,31 @@
+use std::{any::Any, future::Future, panic::AssertUnwindSafe};
+
+use futures::FutureExt;
+
+/// What happens to a strategy or executor after one of its calls panicked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Keep the component running. A strategy first re-syncs through
+    /// [`IStrategy::sync_state`](crate::IStrategy::sync_state), since the panic may have left its state half
+    /// updated; it is quarantined if that fails too.
+    #[default]
+    Restart,
+    /// Stop the component for good. A quarantined strategy receives no more events; a quarantined executor fails
+    /// every action routed to it.
+    Quarantine,
+}
+
+/// Run `future`, turning a panic into its message.
+pub(crate) async fn catch_panic<F: Future>(future: F) -> Result<F::Output, String> {
+    AssertUnwindSafe(future).catch_unwind().await.map_err(panic_message)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&'static str>() {
+            Ok(message) => message.to_string(),
+            Err(_) => "unknown panic payload".to_string(),
+        },
+    }
+}
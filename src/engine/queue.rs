@@ -0,0 +1,331 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::Deserialize;
+use tokio::sync::{Notify, mpsc::UnboundedSender};
+use tracing::{error, warn};
+
+use super::{ExecutionReport, metrics::ExecutorMetrics, trace::ActionTrace};
+use crate::{ActionId, Clock, ExecutionOutcome, IActionSubmitter};
+
+/// What happens to a submitted action when an executor's queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Hold back the submitting strategy until there is room. The action is queued right away, and the strategy
+    /// waits for the queue to drop below its capacity before handling its next input, so no action is ever lost.
+    #[default]
+    Block,
+    /// Discard the oldest queued action to make room.
+    DropOldest,
+    /// Discard the submitted action.
+    DropNewest,
+    /// Reject the submitted action, reporting it to the strategy as [`Failed`](ExecutionOutcome::Failed).
+    Fail,
+}
+
+struct QueueState<A> {
+    actions: VecDeque<A>,
+    closed: bool,
+}
+
+/// A bounded, single-consumer queue of actions in front of one executor.
+pub(crate) struct ActionQueue<A> {
+    name: String,
+    capacity: usize,
+    overflow: OverflowPolicy,
+
+    state: Mutex<QueueState<A>>,
+    action_available: Notify,
+    space_available: Notify,
+}
+
+impl<A> ActionQueue<A> {
+    pub(crate) fn new(name: impl Into<String>, capacity: usize, overflow: OverflowPolicy) -> Self {
+        Self {
+            name: name.into(),
+            capacity: capacity.max(1),
+            overflow,
+            state: Mutex::new(QueueState {
+                actions: VecDeque::new(),
+                closed: false,
+            }),
+            action_available: Notify::new(),
+            space_available: Notify::new(),
+        }
+    }
+
+    /// Queue `action`, or hand it back if the queue is full and its policy is [`OverflowPolicy::Fail`].
+    pub(crate) fn push(&self, action: A) -> Result<(), A> {
+        let mut state = self.state.lock().unwrap();
+
+        if state.closed {
+            error!(name = self.name, "action queue closed, dropping action");
+            return Ok(());
+        }
+
+        if state.actions.len() >= self.capacity {
+            match self.overflow {
+                // The submitter waits for space afterwards, see `wait_for_space`.
+                OverflowPolicy::Block => {}
+                OverflowPolicy::DropOldest => {
+                    warn!(name = self.name, "action queue full, dropping oldest action");
+                    state.actions.pop_front();
+                }
+                OverflowPolicy::DropNewest => {
+                    warn!(name = self.name, "action queue full, dropping action");
+                    return Ok(());
+                }
+                OverflowPolicy::Fail => {
+                    error!(
+                        name = self.name,
+                        capacity = self.capacity,
+                        "action queue full, action rejected"
+                    );
+                    return Err(action);
+                }
+            }
+        }
+
+        state.actions.push_back(action);
+        drop(state);
+
+        self.action_available.notify_one();
+        Ok(())
+    }
+
+    /// Wait until a queue with the [`OverflowPolicy::Block`] policy is below its capacity, or closed.
+    pub(crate) async fn wait_for_space(&self) {
+        if self.overflow != OverflowPolicy::Block {
+            return;
+        }
+
+        loop {
+            // Register before checking, so an action popped in between still wakes us up.
+            let space_available = self.space_available.notified();
+            tokio::pin!(space_available);
+            space_available.as_mut().enable();
+
+            {
+                let state = self.state.lock().unwrap();
+                if state.actions.len() < self.capacity || state.closed {
+                    return;
+                }
+            }
+
+            space_available.await;
+        }
+    }
+
+    /// Wait for the next action. Returns `None` once the queue is closed and drained.
+    pub(crate) async fn pop(&self) -> Option<A> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+
+                if let Some(action) = state.actions.pop_front() {
+                    drop(state);
+                    self.space_available.notify_waiters();
+                    return Some(action);
+                }
+
+                if state.closed {
+                    return None;
+                }
+            }
+
+            self.action_available.notified().await;
+        }
+    }
+
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.action_available.notify_one();
+        self.space_available.notify_waiters();
+    }
+}
+
+/// An action waiting in an executor queue, along with where to send its execution report.
+pub(crate) struct QueuedAction<A> {
+    pub(crate) id: ActionId,
+    pub(crate) action: A,
+    pub(crate) reports: UnboundedSender<ExecutionReport>,
+    /// Set if the action was submitted while a strategy processed an event.
+    pub(crate) trace: Option<ActionTrace>,
+}
+
+/// Fans submitted actions out to every executor queue. The queues are closed when the router is dropped, i.e.
+/// once every strategy has stopped.
+pub(crate) struct ActionRouter<A> {
+    queues: Vec<(Arc<ActionQueue<QueuedAction<A>>>, ExecutorMetrics)>,
+    next_id: AtomicU64,
+    clock: Clock,
+}
+
+impl<A> ActionRouter<A>
+where
+    A: Clone,
+{
+    pub(crate) fn new(queues: Vec<(Arc<ActionQueue<QueuedAction<A>>>, ExecutorMetrics)>, clock: Clock) -> Self {
+        Self {
+            queues,
+            next_id: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// Wait until every executor queue under [`OverflowPolicy::Block`] has room again.
+    pub(crate) async fn wait_for_space(&self) {
+        for (queue, _) in &self.queues {
+            queue.wait_for_space().await;
+        }
+    }
+
+    fn route(&self, action: A, reports: &UnboundedSender<ExecutionReport>) -> ActionId {
+        let id = ActionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let trace = ActionTrace::capture();
+
+        if let Some(((last, last_metrics), rest)) = self.queues.split_last() {
+            for (queue, metrics) in rest {
+                self.push(
+                    queue,
+                    metrics,
+                    QueuedAction {
+                        id,
+                        action: action.clone(),
+                        reports: reports.clone(),
+                        trace: trace.clone(),
+                    },
+                );
+            }
+
+            self.push(
+                last,
+                last_metrics,
+                QueuedAction {
+                    id,
+                    action,
+                    reports: reports.clone(),
+                    trace,
+                },
+            );
+        }
+
+        id
+    }
+
+    fn push(&self, queue: &ActionQueue<QueuedAction<A>>, metrics: &ExecutorMetrics, action: QueuedAction<A>) {
+        metrics.actions_submitted.inc();
+
+        let Err(rejected) = queue.push(action) else {
+            return;
+        };
+
+        metrics.actions_failed.inc();
+
+        // The strategy may have stopped already during shutdown.
+        let _ = rejected.reports.send(ExecutionReport {
+            action_id: rejected.id,
+            correlation_id: rejected.trace.as_ref().map(|trace| trace.origin.event.correlation_id()),
+            executor: queue.name.clone(),
+            outcome: ExecutionOutcome::Failed {
+                error: "action queue full".to_string(),
+                tx_hash: None,
+            },
+            started_at: self.clock.now(),
+            elapsed: Default::default(),
+        });
+    }
+}
+
+impl<A> Drop for ActionRouter<A> {
+    fn drop(&mut self) {
+        for (queue, _) in &self.queues {
+            queue.close();
+        }
+    }
+}
+
+/// The submitter handed to a single strategy, so execution reports find their way back to it.
+pub(crate) struct StrategySubmitter<A> {
+    router: Arc<ActionRouter<A>>,
+    reports: UnboundedSender<ExecutionReport>,
+}
+
+impl<A> StrategySubmitter<A> {
+    pub(crate) fn new(router: Arc<ActionRouter<A>>, reports: UnboundedSender<ExecutionReport>) -> Self {
+        Self { router, reports }
+    }
+}
+
+impl<A> IActionSubmitter<A> for StrategySubmitter<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn submit(&self, action: A) {
+        self.router.route(action, &self.reports);
+    }
+
+    fn submit_tracked(&self, action: A) -> Option<ActionId> {
+        Some(self.router.route(action, &self.reports))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{ActionQueue, OverflowPolicy};
+
+    fn drain(queue: &ActionQueue<u32>) -> Vec<u32> {
+        queue.state.lock().unwrap().actions.drain(..).collect()
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let queue = ActionQueue::new("test", 2, OverflowPolicy::DropOldest);
+        assert!((1..=3).all(|v| queue.push(v).is_ok()));
+        assert_eq!(drain(&queue), vec![2, 3]);
+
+        let queue = ActionQueue::new("test", 2, OverflowPolicy::DropNewest);
+        assert!((1..=3).all(|v| queue.push(v).is_ok()));
+        assert_eq!(drain(&queue), vec![1, 2]);
+
+        let queue = ActionQueue::new("test", 2, OverflowPolicy::Fail);
+        assert_eq!(queue.push(1), Ok(()));
+        assert_eq!(queue.push(2), Ok(()));
+        assert_eq!(queue.push(3), Err(3));
+        assert_eq!(drain(&queue), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_block_waits_for_space() {
+        let queue = Arc::new(ActionQueue::new("test", 1, OverflowPolicy::Block));
+        assert_eq!(queue.push(1), Ok(()));
+        assert_eq!(queue.push(2), Ok(()));
+
+        let producer = tokio::spawn({
+            let queue = queue.clone();
+            async move { queue.wait_for_space().await }
+        });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!producer.is_finished());
+
+        assert_eq!(queue.pop().await, Some(1));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!producer.is_finished());
+
+        assert_eq!(queue.pop().await, Some(2));
+        producer.await.unwrap();
+
+        queue.close();
+        assert_eq!(queue.pop().await, None);
+    }
+}
@@ -0,0 +1,26 @@
+use std::time::{Duration, SystemTime};
+
+use super::CorrelationId;
+use crate::{ActionId, ExecutionOutcome, TxHash};
+
+/// The result of one executor handling one action, delivered to the strategy that submitted the action through
+/// [`IStrategy::on_execution_report`](crate::IStrategy::on_execution_report).
+///
+/// Every executor reports separately, so an action handled by two executors produces two reports. Executors that
+/// skip the action don't report.
+#[derive(Debug, Clone)]
+pub struct ExecutionReport {
+    pub action_id: ActionId,
+    /// The event the strategy was processing when it submitted the action, if any.
+    pub correlation_id: Option<CorrelationId>,
+    pub executor: String,
+    pub outcome: ExecutionOutcome,
+    pub started_at: SystemTime,
+    pub elapsed: Duration,
+}
+
+impl ExecutionReport {
+    pub fn tx_hash(&self) -> Option<TxHash> {
+        self.outcome.tx_hash()
+    }
+}
@@ -0,0 +1,58 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Partition of the events of a sharded strategy; see
+/// [`Engine::add_sharded_strategy`](crate::Engine::add_sharded_strategy). Events with the same key are processed by
+/// the same strategy instance, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShardKey(u64);
+
+impl ShardKey {
+    /// Key of anything hashable, such as a pool address or a transaction sender.
+    pub fn new(key: &impl Hash) -> Self {
+        // `DefaultHasher::new` is not randomly seeded, so keys don't move between shards across runs.
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        Self(hasher.finish())
+    }
+
+    pub fn from_u64(key: u64) -> Self {
+        Self(key)
+    }
+}
+
+/// The shard a strategy instance runs as.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Shard {
+    pub(crate) index: usize,
+    pub(crate) count: usize,
+}
+
+impl Shard {
+    /// Whether this shard processes events with `key`. Events without a key go to every shard.
+    pub(crate) fn owns(&self, key: Option<ShardKey>) -> bool {
+        key.is_none_or(|ShardKey(key)| key % self.count as u64 == self.index as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Shard, ShardKey};
+
+    #[test]
+    fn test_every_key_has_exactly_one_shard() {
+        let shards = (0..4).map(|index| Shard { index, count: 4 }).collect::<Vec<_>>();
+
+        for key in 0..100u64 {
+            let key = ShardKey::new(&key);
+            assert_eq!(shards.iter().filter(|shard| shard.owns(Some(key))).count(), 1);
+        }
+
+        assert!(shards.iter().all(|shard| shard.owns(None)));
+    }
+
+    #[test]
+    fn test_shard_key_is_stable() {
+        assert_eq!(ShardKey::new(&"0xpool"), ShardKey::new(&"0xpool"));
+        assert_eq!(ShardKey::from_u64(7), ShardKey(7));
+    }
+}
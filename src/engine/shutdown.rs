@@ -0,0 +1,58 @@
+use tokio_util::sync::CancellationToken;
+
+/// A cloneable handle that requests a graceful shutdown of a running [`Engine`](crate::Engine).
+///
+/// Shutdown happens in stages: collectors stop first, strategies drain the events already in the
+/// event channel, then executors drain the action channel and finish in-flight actions. Anything
+/// still running once the engine's shutdown timeout elapses is aborted.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    token: CancellationToken,
+}
+
+impl ShutdownHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the engine to shut down. Calling it more than once has no effect.
+    pub fn shutdown(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Wait until a shutdown has been requested.
+    pub async fn wait(&self) {
+        self.token.cancelled().await
+    }
+}
+
+/// Resolve once the process receives SIGINT or, on unix, SIGTERM.
+pub(crate) async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let mut sigterm = match signal(SignalKind::terminate()) {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::error!("fail to install SIGTERM handler: {e:#}");
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = sigterm.recv() => {}
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use eyre::{Result, WrapErr};
+use futures::StreamExt;
+use tracing::{debug, info, warn};
+
+use super::{Envelope, EventMeta, shard::Shard};
+use crate::{ActionId, Clock, IActionSubmitter, ICollector, IStrategy};
+
+/// A strategy's serialized state, from [`IStrategy::snapshot`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub state: Vec<u8>,
+    /// When the last event the state includes was received; events recorded after it are replayed on restore.
+    pub taken_at: SystemTime,
+}
+
+/// Where the engine keeps strategy snapshots, by strategy name.
+#[async_trait]
+pub trait ISnapshotStore: Send + Sync {
+    async fn load(&self, key: &str) -> Result<Option<Snapshot>>;
+
+    async fn save(&self, key: &str, snapshot: Snapshot) -> Result<()>;
+}
+
+/// Keeps each snapshot in a file of `dir`, replaced atomically on save.
+pub struct FileSnapshotStore {
+    dir: PathBuf,
+}
+
+impl FileSnapshotStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.snapshot", file_stem(key)))
+    }
+}
+
+/// A file name for `key`, with anything but alphanumerics, dashes and dots replaced.
+pub(crate) fn file_stem(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[async_trait]
+impl ISnapshotStore for FileSnapshotStore {
+    async fn load(&self, key: &str) -> Result<Option<Snapshot>> {
+        let path = self.path(key);
+
+        let bytes = match tokio::task::spawn_blocking(move || std::fs::read(path)).await? {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).wrap_err_with(|| format!("fail to read snapshot of {key}")),
+        };
+
+        // The time the snapshot was taken, in microseconds since the unix epoch, followed by the state.
+        let Some((taken_at, state)) = bytes.split_first_chunk::<8>() else {
+            eyre::bail!("snapshot of {key} is truncated");
+        };
+
+        Ok(Some(Snapshot {
+            state: state.to_vec(),
+            taken_at: UNIX_EPOCH + Duration::from_micros(u64::from_le_bytes(*taken_at)),
+        }))
+    }
+
+    async fn save(&self, key: &str, snapshot: Snapshot) -> Result<()> {
+        let path = self.path(key);
+        let dir = self.dir.clone();
+
+        let taken_at = snapshot
+            .taken_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+        let mut bytes = Vec::with_capacity(8 + snapshot.state.len());
+        bytes.extend_from_slice(&taken_at.to_le_bytes());
+        bytes.extend_from_slice(&snapshot.state);
+
+        tokio::task::spawn_blocking(move || {
+            std::fs::create_dir_all(&dir)?;
+            let tmp = path.with_extension("snapshot.tmp");
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(tmp, path)
+        })
+        .await?
+        .wrap_err_with(|| format!("fail to save snapshot of {key}"))
+    }
+}
+
+type ReplayFactory<E> = Box<dyn Fn(SystemTime) -> Box<dyn ICollector<E>> + Send + Sync>;
+
+/// How the engine persists and restores strategy state; see
+/// [`Engine::with_snapshots`](crate::Engine::with_snapshots).
+pub struct SnapshotConfig<E> {
+    store: Arc<dyn ISnapshotStore>,
+    interval: Option<Duration>,
+    replay: Option<ReplayFactory<E>>,
+}
+
+impl<E> SnapshotConfig<E> {
+    /// Snapshot strategies to `store` when they stop.
+    pub fn new(store: impl ISnapshotStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            interval: None,
+            replay: None,
+        }
+    }
+
+    /// Also snapshot strategies every `interval`, so a crash loses at most that much.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// After restoring a snapshot, replay the events of the collector built by `replay` from the time the snapshot
+    /// was taken, typically a [`ReplayCollector`](crate::collector::ReplayCollector) of what a
+    /// [`RecordingCollector`](crate::collector::RecordingCollector) recorded. Actions submitted while replaying are
+    /// dropped, since they were already handled before the restart.
+    pub fn with_replay<F>(mut self, replay: F) -> Self
+    where
+        F: Fn(SystemTime) -> Box<dyn ICollector<E>> + Send + Sync + 'static,
+    {
+        self.replay = Some(Box::new(replay));
+        self
+    }
+
+    /// What the task of the strategy named `name` needs to snapshot it. Instances of a sharded strategy are kept
+    /// apart by their index.
+    pub(crate) fn for_strategy(&self, name: &str, shard: Option<Shard>, clock: Clock) -> StrategySnapshots {
+        let key = match shard {
+            Some(shard) => format!("{name}.{}", shard.index),
+            None => name.to_string(),
+        };
+
+        StrategySnapshots {
+            store: self.store.clone(),
+            key,
+            interval: self.interval,
+            clock,
+        }
+    }
+
+    /// Restore `strategy` from its snapshot, if there is one, and replay the events it missed.
+    pub(crate) async fn restore<A>(
+        &self,
+        strategy: &mut Box<dyn IStrategy<E, A>>,
+        snapshots: &StrategySnapshots,
+        shard: Option<Shard>,
+    ) -> Result<()>
+    where
+        E: Send + Sync + Clone + 'static,
+        A: Send + Sync + Clone + 'static,
+    {
+        let name = strategy.name().to_string();
+
+        let Some(snapshot) = self.store.load(&snapshots.key).await? else {
+            debug!(name, "no snapshot to restore");
+            return Ok(());
+        };
+
+        strategy
+            .restore(&snapshot.state)
+            .wrap_err_with(|| format!("fail to restore snapshot of {name}"))?;
+        info!(name, taken_at = ?snapshot.taken_at, "strategy restored from snapshot");
+
+        let Some(replay) = &self.replay else {
+            return Ok(());
+        };
+
+        let collector = replay(snapshot.taken_at);
+        let collector_name: Arc<str> = Arc::from(collector.name());
+        let mut events = collector
+            .get_event_stream()
+            .await
+            .wrap_err("fail to replay events since snapshot")?;
+        let submitter: Arc<dyn IActionSubmitter<A>> = Arc::new(ReplaySubmitter);
+
+        let mut replayed = 0;
+        while let Some(event) = events.next().await {
+            if !strategy.is_interested(&event) || shard.is_some_and(|shard| !shard.owns(strategy.shard_key(&event))) {
+                continue;
+            }
+
+            let envelope = Envelope {
+                meta: EventMeta {
+                    collector: collector_name.clone(),
+                    chain_id: None,
+                    received_at: Instant::now(),
+                    received_time: snapshots.clock.now(),
+                    sequence: replayed,
+                },
+                event,
+            };
+            strategy.process_envelope(envelope, submitter.clone()).await;
+            replayed += 1;
+        }
+
+        info!(name, replayed, "replayed events since snapshot");
+        Ok(())
+    }
+}
+
+/// Saves the snapshots of one strategy.
+pub(crate) struct StrategySnapshots {
+    store: Arc<dyn ISnapshotStore>,
+    key: String,
+    pub(crate) interval: Option<Duration>,
+    clock: Clock,
+}
+
+impl StrategySnapshots {
+    /// Save the state of `strategy`, whose last processed event was received at `last_received`.
+    pub(crate) async fn save<E, A>(&self, strategy: &dyn IStrategy<E, A>, last_received: Option<SystemTime>)
+    where
+        E: Send + Sync + Clone + 'static,
+        A: Send + Sync + Clone + 'static,
+    {
+        let Some(state) = strategy.snapshot() else {
+            return;
+        };
+
+        let snapshot = Snapshot {
+            state,
+            taken_at: last_received.unwrap_or_else(|| self.clock.now()),
+        };
+
+        match self.store.save(&self.key, snapshot).await {
+            Ok(()) => debug!(name = strategy.name(), "strategy snapshot saved"),
+            Err(e) => warn!(name = strategy.name(), "fail to save strategy snapshot: {e:#}"),
+        }
+    }
+}
+
+/// Drops the actions of replayed events.
+struct ReplaySubmitter;
+
+impl<A> IActionSubmitter<A> for ReplaySubmitter
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn submit(&self, _action: A) {}
+
+    fn submit_tracked(&self, _action: A) -> Option<ActionId> {
+        None
+    }
+}
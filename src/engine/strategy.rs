@@ -0,0 +1,273 @@
+use std::{
+    fmt::Debug,
+    sync::Arc,
+    time::{Instant, SystemTime},
+};
+
+use tokio::{
+    sync::{
+        Notify,
+        broadcast::{Receiver, Sender, error::RecvError},
+        mpsc::UnboundedReceiver,
+    },
+    time::Interval,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, debug, error, info, info_span, trace, warn};
+
+use super::{
+    EngineEvent, Envelope, ExecutionReport, PanicPolicy, ShutdownHandle,
+    handle::{Component, ComponentState},
+    metrics::StrategyMetrics,
+    panic::catch_panic,
+    queue::ActionRouter,
+    shard::Shard,
+    snapshot::StrategySnapshots,
+    trace::ActionOrigin,
+};
+use crate::{IActionSubmitter, IStrategy};
+
+/// Everything a strategy task receives: events, engine events and reports for the actions it submitted.
+pub(crate) struct StrategyInputs<E, A> {
+    pub(crate) events: Receiver<Arc<Envelope<E>>>,
+    /// Where the strategy's actions go, to wait for room in the executor queues before handling the next input.
+    pub(crate) actions: Arc<ActionRouter<A>>,
+    /// Set if the strategy is one instance of a sharded strategy, which only processes the events of its shard.
+    pub(crate) shard: Option<Shard>,
+    pub(crate) engine_events: Receiver<EngineEvent>,
+    pub(crate) reports: UnboundedReceiver<ExecutionReport>,
+    /// Set if the engine runs with snapshots.
+    pub(crate) snapshots: Option<StrategySnapshots>,
+}
+
+/// What every strategy task shares with the rest of the engine.
+#[derive(Clone)]
+pub(crate) struct StrategyContext {
+    pub(crate) engine_event_sender: Sender<EngineEvent>,
+    /// Notified whenever the strategy receives an event, to wake finite collectors waiting for room.
+    pub(crate) events_consumed: Arc<Notify>,
+    pub(crate) shutdown: ShutdownHandle,
+    pub(crate) deadline: CancellationToken,
+}
+
+/// Feed events to `strategy` until the event channel closes, the strategy is removed or quarantined, or `deadline`
+/// is cancelled. Engine events and execution reports are delivered in between; events arriving while the strategy
+/// is paused, that it isn't [interested](IStrategy::is_interested) in, or that belong to another shard, are skipped
+/// without being cloned. A panic in any of the strategy's calls is handled according to `panic_policy`. With
+/// snapshots, the strategy's state is saved periodically and when it stops, unless it was quarantined.
+pub(crate) async fn run_strategy<E, A>(
+    mut strategy: Box<dyn IStrategy<E, A>>,
+    component: Arc<Component>,
+    metrics: StrategyMetrics,
+    panic_policy: PanicPolicy,
+    submitter: Arc<dyn IActionSubmitter<A>>,
+    inputs: StrategyInputs<E, A>,
+    context: StrategyContext,
+) where
+    E: Send + Sync + Clone + Debug + 'static,
+    A: Send + Sync + Clone + 'static,
+{
+    let StrategyInputs {
+        events: mut event_receiver,
+        actions,
+        shard,
+        engine_events: mut engine_event_receiver,
+        reports: mut report_receiver,
+        snapshots,
+    } = inputs;
+    let StrategyContext {
+        engine_event_sender,
+        events_consumed,
+        shutdown,
+        deadline,
+    } = context;
+
+    let name = strategy.name().to_string();
+    let source: Arc<str> = Arc::from(strategy.name());
+    debug!(name, "starting strategy...");
+
+    let mut engine_events_open = true;
+    let mut snapshot_ticker = snapshots.as_ref().and_then(|snapshots| {
+        let interval = snapshots.interval?;
+        Some(tokio::time::interval_at(
+            tokio::time::Instant::now() + interval,
+            interval,
+        ))
+    });
+    let mut last_received: Option<SystemTime> = None;
+
+    loop {
+        // Actions submitted under `OverflowPolicy::Block` may have filled an executor queue.
+        tokio::select! {
+            _ = deadline.cancelled() => {
+                warn!(name, "strategy aborted after shutdown timeout");
+                break;
+            }
+            _ = component.removed() => {
+                info!(name, "strategy removed");
+                break;
+            }
+            _ = actions.wait_for_space() => {}
+        }
+
+        // The Debug-formatted input and the panic message, if handling the input panicked.
+        let panicked = tokio::select! {
+            _ = deadline.cancelled() => {
+                warn!(name, "strategy aborted after shutdown timeout");
+                break;
+            }
+            _ = component.removed() => {
+                info!(name, "strategy removed");
+                break;
+            }
+            event = next_event(&mut event_receiver, &events_consumed) => match event {
+                Ok(_) if component.is_paused() => {
+                    debug!(name, "strategy paused, skipping event");
+                    None
+                }
+                Ok(envelope) if !strategy.is_interested(&envelope.event) => None,
+                Ok(envelope) if shard.is_some_and(|shard| !shard.owns(strategy.shard_key(&envelope.event))) => None,
+                Ok(shared) => {
+                    let envelope = Envelope {
+                        meta: shared.meta.clone(),
+                        event: shared.event.clone(),
+                    };
+                    let start = Instant::now();
+                    last_received = Some(shared.meta.received_time);
+
+                    // Actions submitted while processing the event are traced back to it.
+                    let span = info_span!(
+                        "event",
+                        correlation_id = %shared.meta.correlation_id(),
+                        collector = %shared.meta.collector,
+                        strategy = name,
+                    );
+                    span.in_scope(|| trace!(event = ?shared.event, "processing event"));
+
+                    let origin = ActionOrigin {
+                        event: shared.meta.clone(),
+                        strategy: source.clone(),
+                        processing_started_at: start,
+                    };
+                    let process = strategy.process_envelope(envelope, submitter.clone());
+                    let process = ActionOrigin::scope(Some(origin), process).instrument(span);
+
+                    tokio::select! {
+                        _ = deadline.cancelled() => {
+                            warn!(name, "strategy aborted after shutdown timeout");
+                            break;
+                        }
+                        result = catch_panic(process) => {
+                            metrics.observe_process_event(start.elapsed());
+                            metrics.events_processed.inc();
+                            component.record_processed();
+
+                            result.err().map(|panic| (format!("{:?}", shared.event), panic))
+                        }
+                    }
+                }
+                Err(RecvError::Closed) if shutdown.is_shutdown() => {
+                    info!(name, "strategy stopped");
+                    break;
+                }
+                Err(RecvError::Closed) => {
+                    // Every collector gave up, so stop the rest of the engine too.
+                    error!(name, "event channel closed!");
+                    shutdown.shutdown();
+                    break;
+                }
+                Err(RecvError::Lagged(num)) => {
+                    warn!(name, "event channel lagged by {num}");
+                    metrics.events_lagged.inc_by(num);
+                    None
+                }
+            },
+            engine_event = engine_event_receiver.recv(), if engine_events_open => match engine_event {
+                Ok(engine_event) => {
+                    let input = format!("{engine_event:?}");
+                    let result = catch_panic(strategy.on_engine_event(engine_event, submitter.clone())).await;
+                    result.err().map(|panic| (input, panic))
+                }
+                Err(RecvError::Lagged(num)) => {
+                    warn!(name, "engine event channel lagged by {num}");
+                    None
+                }
+                Err(RecvError::Closed) => {
+                    engine_events_open = false;
+                    None
+                }
+            },
+            Some(report) = report_receiver.recv() => {
+                let input = format!("{report:?}");
+                let result = catch_panic(strategy.on_execution_report(report, submitter.clone())).await;
+                result.err().map(|panic| (input, panic))
+            }
+            _ = tick(&mut snapshot_ticker) => {
+                if let Some(snapshots) = &snapshots {
+                    snapshots.save(strategy.as_ref(), last_received).await;
+                }
+                None
+            }
+        };
+
+        let Some((input, panic)) = panicked else {
+            continue;
+        };
+
+        error!(name, input, "strategy panicked: {panic}");
+
+        let quarantined = match panic_policy {
+            PanicPolicy::Restart => match catch_panic(strategy.sync_state(submitter.clone())).await {
+                Ok(Ok(())) => {
+                    info!(name, "strategy restarted after panic");
+                    false
+                }
+                Ok(Err(e)) => {
+                    error!(name, "fail to sync state after panic: {e:#}");
+                    true
+                }
+                Err(panic) => {
+                    error!(name, "strategy panicked again while syncing state: {panic}");
+                    true
+                }
+            },
+            PanicPolicy::Quarantine => true,
+        };
+
+        let _ = engine_event_sender.send(EngineEvent::StrategyPanicked {
+            strategy: name.clone(),
+            input,
+            panic,
+            quarantined,
+        });
+
+        if quarantined {
+            warn!(name, "strategy quarantined");
+            component.set_state(ComponentState::Quarantined);
+            return;
+        }
+    }
+
+    if let Some(snapshots) = &snapshots {
+        snapshots.save(strategy.as_ref(), last_received).await;
+    }
+
+    component.set_state(ComponentState::Stopped);
+}
+
+/// Wait for the next tick of `ticker`, or forever without one.
+async fn tick(ticker: &mut Option<Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Receive the next event, then wake the finite collectors waiting for room in the event channel.
+async fn next_event<T: Clone>(receiver: &mut Receiver<T>, consumed: &Notify) -> Result<T, RecvError> {
+    let event = receiver.recv().await;
+    consumed.notify_waiters();
+    event
+}
@@ -0,0 +1,335 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use tokio::sync::{Notify, broadcast::Sender};
+use tracing::{error, info, warn};
+
+use super::{
+    EngineEvent, Envelope, EventMeta, ShutdownHandle,
+    handle::{Component, ComponentState},
+    metrics::CollectorMetrics,
+};
+use crate::{Clock, ICollector};
+
+/// Decides if and when a collector is restarted after its event stream fails or ends.
+///
+/// The delay before the n-th consecutive restart is `initial_backoff * multiplier^(n - 1)`, capped at
+/// `max_backoff`, with up to `jitter` (a fraction of the delay) randomly added or removed.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    /// `None` retries forever.
+    pub max_retries: Option<u32>,
+    pub jitter: f64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_retries: None,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Never restart; a failed or ended stream stops the collector for good.
+    pub fn never() -> Self {
+        Self {
+            max_retries: Some(0),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = Some(max_retries);
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Whether the `attempt`-th consecutive restart (starting at 1) is allowed.
+    pub fn allows(&self, attempt: u32) -> bool {
+        self.max_retries.is_none_or(|max| attempt <= max)
+    }
+
+    /// Delay before the `attempt`-th consecutive restart (starting at 1), without jitter.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(exponent);
+
+        Duration::from_secs_f64(backoff.min(self.max_backoff.as_secs_f64()))
+    }
+
+    pub(crate) fn backoff_with_jitter(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+
+        if jitter == 0.0 {
+            return backoff;
+        }
+
+        let factor = 1.0 + jitter * (rand::random::<f64>() * 2.0 - 1.0);
+        backoff.mul_f64(factor)
+    }
+}
+
+/// The channels and counters every collector task shares with the rest of the engine.
+#[derive(Clone)]
+pub(crate) struct CollectorContext<E> {
+    pub(crate) event_sender: Sender<Arc<Envelope<E>>>,
+    pub(crate) event_channel_capacity: usize,
+    /// Source of [`EventMeta::sequence`], shared by every collector.
+    pub(crate) next_sequence: Arc<AtomicU64>,
+    pub(crate) clock: Clock,
+    pub(crate) engine_event_sender: Sender<EngineEvent>,
+    pub(crate) remaining_finite: Arc<AtomicUsize>,
+    /// Notified by strategies as they receive events, so finite collectors can wait for room in the event channel.
+    pub(crate) events_consumed: Arc<Notify>,
+    pub(crate) shutdown: ShutdownHandle,
+}
+
+/// Pump events from `collector` into `event_sender`, re-subscribing according to `policy` whenever the stream
+/// fails to start or ends. Returns when a shutdown is requested, the collector is removed, the policy gives up, or
+/// the stream of a finite collector ends; `context.remaining_finite` counts down when a finite collector stops either
+/// way and requests a shutdown once it reaches zero.
+///
+/// Finite collectors can produce events much faster than live ones, so they wait for strategies to catch up
+/// instead of letting the event channel lag.
+pub(crate) async fn run_collector<E>(
+    collector: Box<dyn ICollector<E>>,
+    component: Arc<Component>,
+    metrics: CollectorMetrics,
+    policy: RestartPolicy,
+    context: CollectorContext<E>,
+) {
+    let CollectorContext {
+        event_sender,
+        event_channel_capacity,
+        next_sequence,
+        clock,
+        engine_event_sender,
+        remaining_finite,
+        events_consumed,
+        shutdown,
+    } = context;
+
+    let name = collector.name().to_string();
+    let source: Arc<str> = Arc::from(collector.name());
+
+    let stopped = || async {
+        tokio::select! {
+            _ = shutdown.wait() => {}
+            _ = component.removed() => {}
+        }
+    };
+
+    let mut attempt = 0;
+    let mut down_since: Option<Instant> = None;
+    // Looked up once the first subscription succeeds.
+    let mut chain_id = None;
+
+    'supervise: loop {
+        let stream = tokio::select! {
+            _ = stopped() => break,
+            stream = collector.get_event_stream() => stream,
+        };
+
+        match stream {
+            Ok(mut event_stream) => {
+                component.set_state(ComponentState::Running);
+
+                let chain_id = match chain_id {
+                    Some(chain_id) => chain_id,
+                    None => tokio::select! {
+                        _ = stopped() => break,
+                        id = collector.chain_id() => *chain_id.insert(id),
+                    },
+                };
+
+                if let Some(since) = down_since.take() {
+                    info!(name, attempt, "collector restarted");
+
+                    let _ = engine_event_sender.send(EngineEvent::CollectorRestarted {
+                        collector: name.clone(),
+                        attempt,
+                        downtime: since.elapsed(),
+                    });
+                }
+
+                loop {
+                    let event = tokio::select! {
+                        _ = stopped() => {
+                            info!(name, "collector stopped");
+                            break 'supervise;
+                        }
+                        event = event_stream.next() => event,
+                    };
+
+                    let Some(event) = event else {
+                        break;
+                    };
+
+                    let meta = EventMeta {
+                        collector: source.clone(),
+                        chain_id,
+                        received_at: Instant::now(),
+                        received_time: clock.now(),
+                        sequence: next_sequence.fetch_add(1, Ordering::Relaxed),
+                    };
+
+                    // The stream is healthy again, so the next failure starts a fresh backoff sequence.
+                    attempt = 0;
+
+                    if collector.is_finite() {
+                        loop {
+                            // Register before checking, so a strategy receiving in between still wakes us up.
+                            let consumed = events_consumed.notified();
+                            tokio::pin!(consumed);
+                            consumed.as_mut().enable();
+
+                            if event_sender.len() < event_channel_capacity {
+                                break;
+                            }
+
+                            tokio::select! {
+                                _ = stopped() => break,
+                                _ = consumed => {}
+                            }
+                        }
+                    }
+
+                    match event_sender.send(Arc::new(Envelope { meta, event })) {
+                        Ok(_) => {
+                            component.record_processed();
+                            metrics.events.inc();
+                        }
+                        Err(e) => error!(name, "error sending event: {e:#}"),
+                    }
+                }
+
+                if collector.is_finite() {
+                    info!(name, "collector finished");
+                    finish(&remaining_finite, &shutdown);
+                    break;
+                }
+
+                error!(name, "event stream ended!");
+            }
+            Err(e) => {
+                error!(name, "fail to get event stream: {e:#}");
+            }
+        }
+
+        component.set_state(ComponentState::Restarting);
+        down_since.get_or_insert_with(Instant::now);
+        attempt += 1;
+
+        if !policy.allows(attempt) {
+            error!(name, "collector stopped after {} restart attempts", attempt - 1);
+
+            let _ = engine_event_sender.send(EngineEvent::CollectorStopped {
+                collector: name.clone(),
+                attempts: attempt - 1,
+            });
+
+            // A finite collector that gives up won't produce the rest of its events either.
+            if collector.is_finite() {
+                finish(&remaining_finite, &shutdown);
+            }
+
+            break;
+        }
+
+        let backoff = policy.backoff_with_jitter(attempt);
+        warn!(name, attempt, ?backoff, "restarting collector");
+
+        tokio::select! {
+            _ = stopped() => break,
+            _ = tokio::time::sleep(backoff) => {}
+        }
+    }
+
+    component.set_state(ComponentState::Stopped);
+}
+
+/// Count a finite collector as done, requesting a shutdown once every finite collector is.
+fn finish(remaining_finite: &AtomicUsize, shutdown: &ShutdownHandle) {
+    if remaining_finite.fetch_sub(1, Ordering::AcqRel) == 1 {
+        info!("all collectors finished");
+        shutdown.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RestartPolicy;
+
+    #[test]
+    fn test_restart_policy_backoff() {
+        let policy = RestartPolicy::default()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_secs(1))
+            .with_multiplier(2.0);
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_restart_policy_max_retries() {
+        let policy = RestartPolicy::default().with_max_retries(2);
+        assert!(policy.allows(1));
+        assert!(policy.allows(2));
+        assert!(!policy.allows(3));
+
+        assert!(!RestartPolicy::never().allows(1));
+        assert!(RestartPolicy::default().allows(u32::MAX));
+    }
+
+    #[test]
+    fn test_restart_policy_jitter_bounds() {
+        let policy = RestartPolicy::default()
+            .with_initial_backoff(Duration::from_secs(1))
+            .with_jitter(0.5);
+
+        for _ in 0..100 {
+            let backoff = policy.backoff_with_jitter(1);
+            assert!(backoff >= Duration::from_millis(500) && backoff <= Duration::from_millis(1500));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    fmt::Display,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tracing::Span;
+
+use super::EventMeta;
+
+/// Identifies an event from the moment a collector emits it. Actions submitted while a strategy processes the event,
+/// and their execution, carry the same id: it is recorded on the `event` and `execute` tracing spans and in
+/// [`ExecutionReport::correlation_id`](super::ExecutionReport::correlation_id).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CorrelationId(pub u64);
+
+impl CorrelationId {
+    /// Id of the event being processed by the calling strategy, or that caused the action being executed by the
+    /// calling executor. `None` outside of the engine's strategy and executor tasks, e.g. in tasks they spawn.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(|origin| origin.event.correlation_id()).ok()
+    }
+}
+
+impl Display for CorrelationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "evt-{}", self.0)
+    }
+}
+
+/// The event a strategy is processing, made available to its submitter.
+#[derive(Debug, Clone)]
+pub(crate) struct ActionOrigin {
+    pub(crate) event: EventMeta,
+    pub(crate) strategy: Arc<str>,
+    pub(crate) processing_started_at: Instant,
+}
+
+tokio::task_local! {
+    static CURRENT: ActionOrigin;
+}
+
+impl ActionOrigin {
+    /// The origin of actions submitted from the current task.
+    pub(crate) fn current() -> Option<Self> {
+        CURRENT.try_with(|origin| origin.clone()).ok()
+    }
+
+    /// Run `future` with `origin` as the origin of the actions it submits, and as what
+    /// [`CorrelationId::current`] reports.
+    pub(crate) async fn scope<F: Future>(origin: Option<Self>, future: F) -> F::Output {
+        match origin {
+            Some(origin) => CURRENT.scope(origin, future).await,
+            None => future.await,
+        }
+    }
+}
+
+/// Where a queued action came from: the event and strategy behind it, and the span it was submitted in, which
+/// becomes the parent of the executor's span.
+#[derive(Debug, Clone)]
+pub(crate) struct ActionTrace {
+    pub(crate) origin: ActionOrigin,
+    pub(crate) submitted_at: Instant,
+    pub(crate) span: Span,
+}
+
+impl ActionTrace {
+    pub(crate) fn capture() -> Option<Self> {
+        Some(Self {
+            origin: ActionOrigin::current()?,
+            submitted_at: Instant::now(),
+            span: Span::current(),
+        })
+    }
+
+    /// Time spent in each hop, given when the executor picked the action up.
+    pub(crate) fn hops(&self, execution_started_at: Instant) -> Hops {
+        let origin = &self.origin;
+
+        Hops {
+            delivery: origin.processing_started_at.duration_since(origin.event.received_at),
+            strategy: self.submitted_at.duration_since(origin.processing_started_at),
+            queued: execution_started_at.duration_since(self.submitted_at),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Hops {
+    /// From the collector emitting the event to the strategy starting to process it.
+    pub(crate) delivery: Duration,
+    /// From the strategy starting to process the event to submitting the action.
+    pub(crate) strategy: Duration,
+    /// From the action being submitted to the executor picking it up.
+    pub(crate) queued: Duration,
+}
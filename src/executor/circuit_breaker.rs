@@ -0,0 +1,186 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use tokio::{sync::watch, time::Instant};
+use tracing::{info, warn};
+
+use crate::{ExecutionOutcome, IExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through.
+    Closed,
+    /// Calls fail right away, until the cooldown has passed.
+    Open,
+    /// A single trial call goes through; it closes the circuit if it succeeds and opens it again otherwise.
+    HalfOpen,
+}
+
+struct Breaker {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Instant,
+}
+
+/// Stops calling the wrapped executor after `failure_threshold` consecutive [`Failed`](ExecutionOutcome::Failed)
+/// outcomes, e.g. while a relay is down or banning us. Actions fail right away while the circuit is open; once
+/// `cooldown` has passed, the next action is let through as a trial.
+///
+/// State changes are logged and can be watched through [`subscribe`](Self::subscribe), e.g. to switch strategies to
+/// another relay.
+pub struct CircuitBreaker<A> {
+    inner: Box<dyn IExecutor<A>>,
+    failure_threshold: u32,
+    cooldown: Duration,
+    breaker: Mutex<Breaker>,
+    state_changes: watch::Sender<CircuitState>,
+}
+
+impl<A> CircuitBreaker<A> {
+    pub fn new(executor: Box<dyn IExecutor<A>>, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            inner: executor,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            breaker: Mutex::new(Breaker {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: Instant::now(),
+            }),
+            state_changes: watch::Sender::new(CircuitState::Closed),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.breaker.lock().unwrap().state
+    }
+
+    /// Watch the circuit's state. Subscribe before handing the breaker to the engine.
+    pub fn subscribe(&self) -> watch::Receiver<CircuitState> {
+        self.state_changes.subscribe()
+    }
+
+    /// Whether a call may go through, and if so, whether it is the trial of a half-open circuit.
+    fn admit(&self) -> Option<bool> {
+        let mut breaker = self.breaker.lock().unwrap();
+
+        match breaker.state {
+            CircuitState::Closed => Some(false),
+            CircuitState::Open if breaker.opened_at.elapsed() >= self.cooldown => {
+                self.transition(&mut breaker, CircuitState::HalfOpen);
+                Some(true)
+            }
+            CircuitState::Open | CircuitState::HalfOpen => None,
+        }
+    }
+
+    fn record(&self, outcome: &ExecutionOutcome, trial: bool) {
+        let failed = matches!(outcome, ExecutionOutcome::Failed { .. });
+        let mut breaker = self.breaker.lock().unwrap();
+
+        if trial {
+            let state = if failed {
+                CircuitState::Open
+            } else {
+                CircuitState::Closed
+            };
+            self.transition(&mut breaker, state);
+        } else if breaker.state == CircuitState::Closed {
+            if !failed {
+                breaker.consecutive_failures = 0;
+                return;
+            }
+
+            breaker.consecutive_failures += 1;
+            if breaker.consecutive_failures >= self.failure_threshold {
+                self.transition(&mut breaker, CircuitState::Open);
+            }
+        }
+    }
+
+    /// Open the circuit again after a trial that ended without an outcome.
+    fn abandon_trial(&self) {
+        let mut breaker = self.breaker.lock().unwrap();
+
+        if breaker.state == CircuitState::HalfOpen {
+            warn!(name = self.inner.name(), "trial action cancelled or panicked");
+            self.transition(&mut breaker, CircuitState::Open);
+        }
+    }
+
+    fn transition(&self, breaker: &mut Breaker, state: CircuitState) {
+        let name = self.inner.name();
+
+        match state {
+            CircuitState::Open => {
+                warn!(name, cooldown = ?self.cooldown, "circuit opened");
+                breaker.opened_at = Instant::now();
+            }
+            CircuitState::HalfOpen => info!(name, "circuit half-open, trying an action"),
+            CircuitState::Closed => info!(name, "circuit closed"),
+        }
+
+        breaker.state = state;
+        breaker.consecutive_failures = 0;
+        self.state_changes.send_replace(state);
+    }
+}
+
+#[async_trait]
+impl<A> IExecutor<A> for CircuitBreaker<A>
+where
+    A: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_interested(&self, action: &A) -> bool {
+        self.inner.is_interested(action)
+    }
+
+    async fn execute(&self, action: A) -> eyre::Result<()> {
+        self.execute_with_outcome(action).await.into_result()
+    }
+
+    async fn execute_with_outcome(&self, action: A) -> ExecutionOutcome {
+        let Some(trial) = self.admit() else {
+            return ExecutionOutcome::Failed {
+                error: "circuit open".to_string(),
+                tx_hash: None,
+            };
+        };
+
+        // Dropped without an outcome if the trial is cancelled or panics, which would leave the circuit half-open.
+        let trial = trial.then_some(Trial {
+            breaker: self,
+            recorded: false,
+        });
+
+        let outcome = self.inner.execute_with_outcome(action).await;
+
+        match trial {
+            Some(mut trial) => {
+                self.record(&outcome, true);
+                trial.recorded = true;
+            }
+            None => self.record(&outcome, false),
+        }
+
+        outcome
+    }
+}
+
+/// The trial call of a half-open circuit, which opens the circuit again if it ends without a recorded outcome.
+struct Trial<'a, A> {
+    breaker: &'a CircuitBreaker<A>,
+    recorded: bool,
+}
+
+impl<A> Drop for Trial<'_, A> {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breaker.abandon_trial();
+        }
+    }
+}
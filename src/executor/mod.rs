@@ -1,4 +1,8 @@
+pub mod circuit_breaker;
 pub mod dummy;
+pub mod rate_limit;
+pub mod retry;
+pub mod timeout;
 
 #[cfg(feature = "evm")]
 pub mod raw_transaction;
@@ -8,3 +12,8 @@ pub mod transaction;
 
 #[cfg(feature = "telegram")]
 pub mod telegram_message;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
+pub use rate_limit::RateLimit;
+pub use retry::Retry;
+pub use timeout::Timeout;
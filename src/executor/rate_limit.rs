@@ -0,0 +1,91 @@
+use std::{sync::Mutex, time::Duration};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::{ExecutionOutcome, IExecutor};
+
+/// Limits how often the wrapped executor is called, with a token bucket: up to `burst` calls at once, refilled at
+/// `rate` calls per second. Calls beyond the limit wait for a token instead of failing, so they hold up the executor's
+/// queue; see [`ExecutorConfig`](crate::engine::ExecutorConfig) for what happens when it fills up.
+pub struct RateLimit<A> {
+    inner: Box<dyn IExecutor<A>>,
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl<A> RateLimit<A> {
+    /// Allow `rate` calls per second, in bursts of up to `burst`.
+    pub fn new(executor: Box<dyn IExecutor<A>>, rate: f64, burst: u32) -> Self {
+        assert!(rate > 0.0, "rate limit must allow some calls");
+        let burst = f64::from(burst.max(1));
+
+        Self {
+            inner: executor,
+            rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Allow `calls` per `period`, without bursts.
+    pub fn per(executor: Box<dyn IExecutor<A>>, calls: u32, period: Duration) -> Self {
+        Self::new(executor, f64::from(calls) / period.as_secs_f64(), 1)
+    }
+
+    /// Take a token, or return how long until one is available.
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let mut bucket = self.bucket.lock().unwrap();
+
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled_at).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.refilled_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+
+    async fn acquire(&self) {
+        while let Err(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<A> IExecutor<A> for RateLimit<A>
+where
+    A: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_interested(&self, action: &A) -> bool {
+        self.inner.is_interested(action)
+    }
+
+    async fn execute(&self, action: A) -> eyre::Result<()> {
+        self.acquire().await;
+        self.inner.execute(action).await
+    }
+
+    async fn execute_with_outcome(&self, action: A) -> ExecutionOutcome {
+        self.acquire().await;
+        self.inner.execute_with_outcome(action).await
+    }
+}
@@ -4,23 +4,35 @@ use alloy::{
 };
 use async_trait::async_trait;
 use eyre::Result;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use crate::IExecutor;
+use super::transaction::wait_for_receipt;
+use crate::{ExecutionOutcome, IExecutor};
 
 pub struct RawTransactionSender {
     provider: Arc<dyn Provider>,
+    receipt_timeout: Option<Duration>,
 }
 
 impl RawTransactionSender {
     pub fn new(provider: Arc<dyn Provider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            receipt_timeout: None,
+        }
     }
 
     pub fn new_http(url: &str) -> Self {
         let provider = ProviderBuilder::default().connect_http(url.parse().unwrap());
-        let provider = Arc::new(provider);
-        Self { provider }
+        Self::new(Arc::new(provider))
+    }
+
+    /// Wait up to `timeout` for the receipt of each sent transaction, reporting it as
+    /// [`Reverted`](ExecutionOutcome::Reverted) if it failed on chain. By default a transaction succeeds as soon as
+    /// the node accepts it.
+    pub fn with_receipt(mut self, timeout: Duration) -> Self {
+        self.receipt_timeout = Some(timeout);
+        self
     }
 
     pub fn new_with_flashbots() -> Self {
@@ -42,27 +54,44 @@ impl RawTransactionSender {
     pub fn new_with_arbitrum_sequencer() -> Self {
         Self::new_http("https://arb1-sequencer.arbitrum.io/rpc")
     }
-}
-
-#[async_trait]
-impl IExecutor<Bytes> for RawTransactionSender {
-    fn name(&self) -> &str {
-        "Raw Transaction Sender"
-    }
 
-    async fn execute(&self, action: Bytes) -> Result<()> {
+    async fn send(&self, action: Bytes) -> ExecutionOutcome {
         let send_result = self.provider.send_raw_transaction(&action).await;
 
         match send_result {
             Ok(tx) => {
                 tracing::info!("sent tx: {:#?}", tx);
+
+                let tx_hash = *tx.tx_hash();
+                match self.receipt_timeout {
+                    Some(timeout) => wait_for_receipt(self.provider.as_ref(), tx_hash, timeout).await,
+                    None => ExecutionOutcome::Succeeded { tx_hash: Some(tx_hash) },
+                }
             }
             Err(err) => {
                 let tx_hash = keccak256(&action);
                 tracing::error!("failed to send tx: {:#}, tx hash: {:#}", err, tx_hash);
+
+                ExecutionOutcome::Failed {
+                    error: format!("{err:#}"),
+                    tx_hash: Some(tx_hash),
+                }
             }
         }
+    }
+}
+
+#[async_trait]
+impl IExecutor<Bytes> for RawTransactionSender {
+    fn name(&self) -> &str {
+        "Raw Transaction Sender"
+    }
+
+    async fn execute(&self, action: Bytes) -> Result<()> {
+        self.send(action).await.into_result()
+    }
 
-        Ok(())
+    async fn execute_with_outcome(&self, action: Bytes) -> ExecutionOutcome {
+        self.send(action).await
     }
 }
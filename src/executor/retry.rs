@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::warn;
+
+use crate::{ExecutionOutcome, IExecutor, engine::RestartPolicy};
+
+type Classifier = Arc<dyn Fn(&ExecutionOutcome) -> bool + Send + Sync>;
+
+/// Retries of a [`Retry`] whose policy doesn't set `max_retries`.
+const DEFAULT_MAX_RETRIES: u32 = 1;
+
+/// Retries failed actions with exponential backoff.
+///
+/// Backoff and the number of retries follow a [`RestartPolicy`]; a policy without `max_retries` retries once, so a
+/// single action can't hold up the executor's queue forever. By default only transient failures are retried, see
+/// [`is_transient`]; a classifier can pick others, so e.g. a reverted or underpriced transaction isn't sent again
+/// while a missing signer isn't retried at all.
+///
+/// ```ignore
+/// let policy = RestartPolicy::default().with_max_retries(3);
+/// let sender = Retry::new(Box::new(RawTransactionSender::new_with_48club()), policy)
+///     .with_classifier(|outcome| matches!(outcome, ExecutionOutcome::Failed { error, .. } if error.contains("429")));
+/// ```
+pub struct Retry<A> {
+    inner: Box<dyn IExecutor<A>>,
+    policy: RestartPolicy,
+    classifier: Classifier,
+}
+
+impl<A> Retry<A> {
+    pub fn new(executor: Box<dyn IExecutor<A>>, mut policy: RestartPolicy) -> Self {
+        policy.max_retries.get_or_insert(DEFAULT_MAX_RETRIES);
+
+        Self {
+            inner: executor,
+            policy,
+            classifier: Arc::new(is_transient),
+        }
+    }
+
+    /// Only retry outcomes for which `classifier` returns `true`.
+    pub fn with_classifier<F>(mut self, classifier: F) -> Self
+    where
+        F: Fn(&ExecutionOutcome) -> bool + Send + Sync + 'static,
+    {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+}
+
+#[async_trait]
+impl<A> IExecutor<A> for Retry<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_interested(&self, action: &A) -> bool {
+        self.inner.is_interested(action)
+    }
+
+    async fn execute(&self, action: A) -> eyre::Result<()> {
+        self.execute_with_outcome(action).await.into_result()
+    }
+
+    async fn execute_with_outcome(&self, action: A) -> ExecutionOutcome {
+        let mut attempt = 0;
+
+        loop {
+            let outcome = self.inner.execute_with_outcome(action.clone()).await;
+
+            if !(self.classifier)(&outcome) {
+                return outcome;
+            }
+
+            attempt += 1;
+            if !self.policy.allows(attempt) {
+                return outcome;
+            }
+
+            let backoff = self.policy.backoff_with_jitter(attempt);
+            warn!(name = self.name(), attempt, ?backoff, ?outcome, "retrying action");
+
+            tokio::time::sleep(backoff).await;
+        }
+    }
+}
+
+/// Whether `outcome` is a failure worth trying again: a timeout, a transport error or a rate limit. This is the
+/// default classifier of [`Retry`].
+pub fn is_transient(outcome: &ExecutionOutcome) -> bool {
+    const PATTERNS: [&str; 11] = [
+        "timed out",
+        "timeout",
+        "429",
+        "too many requests",
+        "rate limit",
+        "connection",
+        "transport error",
+        "error sending request",
+        "502 bad gateway",
+        "503 service unavailable",
+        "504 gateway timeout",
+    ];
+
+    let ExecutionOutcome::Failed { error, .. } = outcome else {
+        return false;
+    };
+
+    let error = error.to_lowercase();
+    PATTERNS.iter().any(|pattern| error.contains(pattern))
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use eyre::eyre;
+
+use crate::{ExecutionOutcome, IExecutor};
+
+/// Fails a call to the wrapped executor that takes longer than `timeout`, e.g. a relay that stopped answering.
+pub struct Timeout<A> {
+    inner: Box<dyn IExecutor<A>>,
+    timeout: Duration,
+}
+
+impl<A> Timeout<A> {
+    pub fn new(executor: Box<dyn IExecutor<A>>, timeout: Duration) -> Self {
+        Self {
+            inner: executor,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl<A> IExecutor<A> for Timeout<A>
+where
+    A: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_interested(&self, action: &A) -> bool {
+        self.inner.is_interested(action)
+    }
+
+    async fn execute(&self, action: A) -> eyre::Result<()> {
+        match tokio::time::timeout(self.timeout, self.inner.execute(action)).await {
+            Ok(result) => result,
+            Err(_) => Err(eyre!("timed out after {:?}", self.timeout)),
+        }
+    }
+
+    async fn execute_with_outcome(&self, action: A) -> ExecutionOutcome {
+        match tokio::time::timeout(self.timeout, self.inner.execute_with_outcome(action)).await {
+            Ok(outcome) => outcome,
+            Err(_) => ExecutionOutcome::Failed {
+                error: format!("timed out after {:?}", self.timeout),
+                tx_hash: None,
+            },
+        }
+    }
+}
@@ -1,20 +1,22 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use alloy::{
     network::{EthereumWallet, TransactionBuilder, eip2718::Encodable2718},
     primitives::{Address, Bytes, keccak256},
-    providers::{Provider, RootProvider},
+    providers::{PendingTransactionBuilder, Provider, RootProvider},
     rpc::types::eth::TransactionRequest,
     signers::local::PrivateKeySigner,
 };
 
-use crate::IExecutor;
+use crate::{ExecutionOutcome, IExecutor, TxHash};
 
 pub struct TransactionSender {
     provider: Arc<dyn Provider>,
     signers: HashMap<Address, EthereumWallet>,
     tx_submission_provider: Option<Arc<dyn Provider>>,
+    receipt_timeout: Option<Duration>,
 }
 
 impl TransactionSender {
@@ -32,6 +34,7 @@ impl TransactionSender {
             provider,
             signers,
             tx_submission_provider: None,
+            receipt_timeout: None,
         }
     }
 }
@@ -55,6 +58,7 @@ impl TransactionSender {
             provider,
             signers,
             tx_submission_provider: Some(tx_submission_provider),
+            receipt_timeout: None,
         }
     }
 
@@ -87,22 +91,28 @@ impl TransactionSender {
     pub fn new_with_arbitrum_sequencer(provider: Arc<dyn Provider>, signers: Vec<PrivateKeySigner>) -> Self {
         Self::new_http_dedicated(provider, "https://arb1-sequencer.arbitrum.io/rpc", signers)
     }
-}
 
-#[async_trait::async_trait]
-impl IExecutor<TransactionRequest> for TransactionSender {
-    fn name(&self) -> &str {
-        "Transaction Sender"
+    /// Wait up to `timeout` for the receipt of each sent transaction, reporting it as
+    /// [`Reverted`](ExecutionOutcome::Reverted) if it failed on chain. By default a transaction succeeds as soon as
+    /// the node accepts it.
+    pub fn with_receipt(mut self, timeout: Duration) -> Self {
+        self.receipt_timeout = Some(timeout);
+        self
     }
+}
 
-    async fn execute(&self, action: TransactionRequest) -> eyre::Result<()> {
+impl TransactionSender {
+    async fn send(&self, action: TransactionRequest) -> ExecutionOutcome {
         let mut action = action;
 
         let account = match action.from {
             Some(v) => v,
             None => {
                 tracing::error!("missing sender address");
-                return Ok(());
+                return ExecutionOutcome::Failed {
+                    error: "missing sender address".to_string(),
+                    tx_hash: None,
+                };
             }
         };
 
@@ -110,7 +120,10 @@ impl IExecutor<TransactionRequest> for TransactionSender {
             Some(v) => v,
             None => {
                 tracing::error!("missing signer for {:#x}", account);
-                return Ok(());
+                return ExecutionOutcome::Failed {
+                    error: format!("missing signer for {account:#x}"),
+                    tx_hash: None,
+                };
             }
         };
 
@@ -119,7 +132,10 @@ impl IExecutor<TransactionRequest> for TransactionSender {
                 Ok(v) => v,
                 Err(err) => {
                     tracing::error!(?account, "failed to get nonce: {err:#}");
-                    return Ok(());
+                    return ExecutionOutcome::Failed {
+                        error: format!("failed to get nonce: {err:#}"),
+                        tx_hash: None,
+                    };
                 }
             };
 
@@ -130,7 +146,10 @@ impl IExecutor<TransactionRequest> for TransactionSender {
             Ok(v) => v.encoded_2718().into(),
             Err(err) => {
                 tracing::error!(?account, "failed to build tx: {err:#}");
-                return Ok(());
+                return ExecutionOutcome::Failed {
+                    error: format!("failed to build tx: {err:#}"),
+                    tx_hash: None,
+                };
             }
         };
 
@@ -149,12 +168,56 @@ impl IExecutor<TransactionRequest> for TransactionSender {
             Err(err) => {
                 let hash = keccak256(&raw_tx);
                 tracing::error!(?account, tx = ?hash, "failed to send tx: {err:#}");
-                return Ok(());
+                return ExecutionOutcome::Failed {
+                    error: format!("failed to send tx: {err:#}"),
+                    tx_hash: Some(hash),
+                };
             }
         };
 
         tracing::info!(?account, "sent tx: {:#x}", tx_hash);
 
-        Ok(())
+        match self.receipt_timeout {
+            Some(timeout) => wait_for_receipt(self.provider.as_ref(), tx_hash, timeout).await,
+            None => ExecutionOutcome::Succeeded { tx_hash: Some(tx_hash) },
+        }
+    }
+}
+
+/// The outcome of a sent transaction according to its receipt.
+pub(crate) async fn wait_for_receipt(provider: &dyn Provider, tx_hash: TxHash, timeout: Duration) -> ExecutionOutcome {
+    let receipt = PendingTransactionBuilder::new(provider.root().clone(), tx_hash)
+        .with_timeout(Some(timeout))
+        .get_receipt()
+        .await;
+
+    match receipt {
+        Ok(receipt) if receipt.status() => ExecutionOutcome::Succeeded { tx_hash: Some(tx_hash) },
+        Ok(_) => {
+            tracing::warn!("tx reverted: {:#x}", tx_hash);
+            ExecutionOutcome::Reverted { tx_hash }
+        }
+        Err(err) => {
+            tracing::error!("failed to get receipt: {:#}, tx hash: {:#x}", err, tx_hash);
+            ExecutionOutcome::Failed {
+                error: format!("failed to get receipt: {err:#}"),
+                tx_hash: Some(tx_hash),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl IExecutor<TransactionRequest> for TransactionSender {
+    fn name(&self) -> &str {
+        "Transaction Sender"
+    }
+
+    async fn execute(&self, action: TransactionRequest) -> eyre::Result<()> {
+        self.send(action).await.into_result()
+    }
+
+    async fn execute_with_outcome(&self, action: TransactionRequest) -> ExecutionOutcome {
+        self.send(action).await
     }
 }
@@ -1,6 +1,25 @@
+use std::fmt::Display;
+
+/// Identifies an action submitted to the engine, see [`IActionSubmitter::submit_tracked`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ActionId(pub u64);
+
+impl Display for ActionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
 pub trait IActionSubmitter<A>: Send + Sync
 where
     A: Send + Sync + Clone + 'static,
 {
     fn submit(&self, action: A);
+
+    /// Submit `action` and return the id that execution reports for it will carry, if the submitter tracks
+    /// actions.
+    fn submit_tracked(&self, action: A) -> Option<ActionId> {
+        self.submit(action);
+        None
+    }
 }
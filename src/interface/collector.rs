@@ -12,5 +12,41 @@ pub trait ICollector<E>: Send + Sync {
         "Unnamed"
     }
 
+    /// Whether the event stream is expected to end, e.g. when replaying a recording. The engine doesn't restart a
+    /// finite collector whose stream ended, and shuts down once every collector is finite and finished.
+    fn is_finite(&self) -> bool {
+        false
+    }
+
+    /// Chain the collector follows, attached to its events as [`EventMeta::chain_id`](crate::engine::EventMeta).
+    /// Looked up once, when the engine first subscribes.
+    async fn chain_id(&self) -> Option<u64> {
+        None
+    }
+
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>>;
 }
+
+/// Lets boxed collectors be wrapped again, e.g. by nesting [`map_collector!`](crate::map_collector) and
+/// [`batch_collector!`](crate::batch_collector).
+#[async_trait]
+impl<E, C> ICollector<E> for Box<C>
+where
+    C: ICollector<E> + ?Sized,
+{
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn is_finite(&self) -> bool {
+        (**self).is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        (**self).chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        (**self).get_event_stream().await
+    }
+}
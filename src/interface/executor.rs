@@ -1,5 +1,54 @@
 use async_trait::async_trait;
-use eyre::Result;
+use eyre::{Result, eyre};
+
+/// Hash of a transaction sent by an executor.
+#[cfg(feature = "evm")]
+pub type TxHash = alloy::primitives::B256;
+/// Hash of a transaction sent by an executor.
+#[cfg(not(feature = "evm"))]
+pub type TxHash = [u8; 32];
+
+/// What happened to an action handed to an executor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionOutcome {
+    /// The action was carried out. For transactions this means the node accepted it.
+    Succeeded {
+        tx_hash: Option<TxHash>,
+    },
+    /// The transaction was included but reverted.
+    Reverted {
+        tx_hash: TxHash,
+    },
+    Failed {
+        error: String,
+        tx_hash: Option<TxHash>,
+    },
+    /// The executor does not handle this action, e.g. an [`ExecutorMap`](crate::ExecutorMap) for another variant.
+    Skipped,
+}
+
+impl ExecutionOutcome {
+    pub fn tx_hash(&self) -> Option<TxHash> {
+        match self {
+            Self::Succeeded { tx_hash } | Self::Failed { tx_hash, .. } => *tx_hash,
+            Self::Reverted { tx_hash } => Some(*tx_hash),
+            Self::Skipped => None,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Succeeded { .. })
+    }
+
+    /// The outcome as returned by [`IExecutor::execute`]: an error if the action failed or reverted.
+    pub fn into_result(self) -> Result<()> {
+        match self {
+            Self::Succeeded { .. } | Self::Skipped => Ok(()),
+            Self::Reverted { .. } => Err(eyre!("transaction reverted")),
+            Self::Failed { error, .. } => Err(eyre!(error)),
+        }
+    }
+}
 
 #[async_trait]
 pub trait IExecutor<A>: Send + Sync {
@@ -8,4 +57,27 @@ pub trait IExecutor<A>: Send + Sync {
     }
 
     async fn execute(&self, action: A) -> Result<()>;
+
+    /// Whether the executor handles `action` at all. Actions it doesn't handle are skipped without a report, even
+    /// while the executor is quarantined. [`ExecutorMap`](crate::ExecutorMap) only handles the actions it extracts, once given
+    /// [`with_ref`](crate::ExecutorMap::with_ref).
+    fn is_interested(&self, _action: &A) -> bool {
+        true
+    }
+
+    /// Execute `action` and describe the outcome. The engine calls this instead of [`IExecutor::execute`] and
+    /// reports the outcome back to the strategy that submitted the action. The default implementation maps the
+    /// result of [`IExecutor::execute`].
+    async fn execute_with_outcome(&self, action: A) -> ExecutionOutcome
+    where
+        A: Send + 'async_trait,
+    {
+        match self.execute(action).await {
+            Ok(()) => ExecutionOutcome::Succeeded { tx_hash: None },
+            Err(e) => ExecutionOutcome::Failed {
+                error: format!("{e:#}"),
+                tx_hash: None,
+            },
+        }
+    }
 }
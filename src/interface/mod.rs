@@ -2,9 +2,11 @@ pub mod action_submitter;
 pub mod collector;
 pub mod executor;
 pub mod strategy;
+pub mod variant;
 
-pub use action_submitter::IActionSubmitter;
+pub use action_submitter::{ActionId, IActionSubmitter};
 pub(crate) use collector::CollectorStream;
 pub use collector::ICollector;
-pub use executor::IExecutor;
+pub use executor::{ExecutionOutcome, IExecutor, TxHash};
 pub use strategy::IStrategy;
+pub use variant::IVariant;
@@ -3,7 +3,10 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use eyre::Result;
 
-use crate::IActionSubmitter;
+use crate::{
+    IActionSubmitter,
+    engine::{EngineEvent, Envelope, ExecutionReport, ShardKey},
+};
 
 #[async_trait]
 pub trait IStrategy<E, A>: Send + Sync
@@ -19,5 +22,47 @@ where
         Ok(())
     }
 
+    /// The strategy's state, serialized to be restored by [`restore`](Self::restore) after a restart when the engine
+    /// runs [with snapshots](crate::Engine::with_snapshots). `None` opts out.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Load a state returned by [`snapshot`](Self::snapshot). Called before [`sync_state`](Self::sync_state).
+    fn restore(&mut self, _snapshot: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether to deliver `event` to [`process_event`](Self::process_event). Events are shared between strategies
+    /// and only cloned for those interested in them, so filtering here is much cheaper than discarding events in
+    /// `process_event`. [`interested_in!`](crate::interested_in) builds a filter from event variants.
+    fn is_interested(&self, _event: &E) -> bool {
+        true
+    }
+
+    /// Which shard processes `event` when the strategy was added with
+    /// [`Engine::add_sharded_strategy`](crate::Engine::add_sharded_strategy), e.g. the pool or sender it is about.
+    /// `None` delivers the event to every shard, for events all of them need such as new blocks.
+    fn shard_key(&self, _event: &E) -> Option<ShardKey> {
+        None
+    }
+
+    /// Called for every event the strategy is interested in, unless it overrides
+    /// [`process_envelope`](Self::process_envelope).
     async fn process_event(&mut self, event: E, submitter: Arc<dyn IActionSubmitter<A>>);
+
+    /// Like [`process_event`](Self::process_event), along with where the event came from and when it was received,
+    /// e.g. to tell how stale a mempool transaction is before acting on it. The default implementation forwards the
+    /// event to `process_event`.
+    async fn process_envelope(&mut self, envelope: Envelope<E>, submitter: Arc<dyn IActionSubmitter<A>>) {
+        self.process_event(envelope.event, submitter).await
+    }
+
+    /// Called when the engine reports something about itself, e.g. a collector restart that may have caused
+    /// missed events.
+    async fn on_engine_event(&mut self, _event: EngineEvent, _submitter: Arc<dyn IActionSubmitter<A>>) {}
+
+    /// Called when an executor has handled an action this strategy submitted. Use
+    /// [`IActionSubmitter::submit_tracked`] to learn the id of a submitted action.
+    async fn on_execution_report(&mut self, _report: ExecutionReport, _submitter: Arc<dyn IActionSubmitter<A>>) {}
 }
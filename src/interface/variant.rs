@@ -0,0 +1,13 @@
+/// One variant of an event or action enum, as a type, so components can be registered for it with
+/// [`Engine::add_collector_for`](crate::Engine::add_collector_for) and friends. Implemented by
+/// `#[derive(HarpoonEvent)]` and `#[derive(HarpoonAction)]` for every variant holding a single value.
+pub trait IVariant<T> {
+    /// What the variant holds.
+    type Payload;
+
+    fn wrap(payload: Self::Payload) -> T;
+
+    fn extract(value: T) -> Option<Self::Payload>;
+
+    fn extract_ref(value: &T) -> Option<&Self::Payload>;
+}
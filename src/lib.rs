@@ -1,5 +1,7 @@
 pub mod action_submitter;
 pub mod collector;
+#[cfg(feature = "config")]
+pub mod config;
 pub mod engine;
 pub mod executor;
 pub mod interface;
@@ -8,9 +10,12 @@ pub mod misc;
 pub mod proxy_detect;
 pub mod save_from_etherscan;
 pub mod service;
+pub mod testing;
 
 pub use async_trait::async_trait;
 pub use engine::Engine;
+#[cfg(feature = "derive")]
+pub use harpoon_derive::{HarpoonAction, HarpoonEvent};
 pub use interface::*;
 pub use misc::*;
 pub use service::*;
@@ -1,10 +1,16 @@
 #[macro_export]
 macro_rules! map_boxed_executor {
     ($executor: expr, $variant: path) => {
-        Box::new($crate::ExecutorMap::new($executor, |action| match action {
-            $variant(value) => Some(value),
-            _ => None,
-        }))
+        Box::new(
+            $crate::ExecutorMap::new($executor, |action| match action {
+                $variant(value) => Some(value),
+                _ => None,
+            })
+            .with_ref(|action| match action {
+                $variant(value) => Some(value),
+                _ => None,
+            }),
+        )
     };
 }
 
@@ -29,9 +35,114 @@ macro_rules! map_collector {
     };
 }
 
+/// Merge collectors of the same event type, see [`CollectorMerge`](crate::CollectorMerge).
+#[macro_export]
+macro_rules! merge_collectors {
+    ($($collector: expr),+ $(,)?) => {
+        Box::new($crate::CollectorMerge::new(vec![
+            $(Box::new($collector) as Box<dyn $crate::ICollector<_>>),+
+        ]))
+    };
+}
+
+#[macro_export]
+macro_rules! throttle_collector {
+    ($collector: expr, $interval: expr) => {
+        Box::new($crate::CollectorThrottle::new(Box::new($collector), $interval))
+    };
+}
+
+#[macro_export]
+macro_rules! sample_collector {
+    ($collector: expr, $interval: expr) => {
+        Box::new($crate::CollectorSample::new(Box::new($collector), $interval))
+    };
+}
+
+#[macro_export]
+macro_rules! debounce_collector {
+    ($collector: expr, $quiet: expr) => {
+        Box::new($crate::CollectorDebounce::new(Box::new($collector), $quiet))
+    };
+}
+
+/// Batch a collector's events into `Vec`s, see [`CollectorBatch`](crate::CollectorBatch). Combines with
+/// [`map_collector!`]:
+///
+/// ```ignore
+/// engine.add_collector(map_collector!(
+///     batch_collector!(log_collector, 100, Duration::from_millis(50)),
+///     Event::Logs
+/// ));
+/// ```
+#[macro_export]
+macro_rules! batch_collector {
+    ($collector: expr, $max_size: expr, $max_wait: expr) => {
+        Box::new($crate::CollectorBatch::new(Box::new($collector), $max_size, $max_wait))
+    };
+}
+
+#[macro_export]
+macro_rules! async_filter_map_collector {
+    ($collector: expr, $f: expr) => {
+        Box::new($crate::CollectorAsyncFilterMap::new(Box::new($collector), $f))
+    };
+}
+
+#[macro_export]
+macro_rules! map_boxed_strategy {
+    ($strategy: expr, $event: path, $action: path) => {
+        Box::new($crate::StrategyMap::new(
+            $strategy,
+            |event| match event {
+                $event(value) => Some(value),
+                _ => None,
+            },
+            $action,
+        ))
+    };
+}
+
+/// Run a strategy on one variant of the engine's event enum, wrapping its actions in a variant of the action enum,
+/// see [`StrategyMap`](crate::StrategyMap):
+///
+/// ```ignore
+/// engine.add_strategy(map_strategy!(LargeTransferAlert::new(threshold), Event::Log, Action::Message));
+/// ```
+#[macro_export]
+macro_rules! map_strategy {
+    ($strategy: expr, $event: path, $action: path) => {
+        $crate::map_boxed_strategy!(Box::new($strategy), $event, $action)
+    };
+}
+
 #[macro_export]
 macro_rules! submit_action {
     ($submitter: expr, $variant: path, $action: expr) => {
         $submitter.submit($variant($action));
     };
 }
+
+/// Whether `$event` is one of the given variants of an event enum, typically the ones collectors were mapped to with
+/// [`map_collector!`]. Meant for [`IStrategy::is_interested`](crate::IStrategy::is_interested):
+///
+/// ```
+/// use harpoon::interested_in;
+///
+/// enum Event {
+///     Block(u64),
+///     Log(String),
+///     Tick(()),
+/// }
+///
+/// // In `is_interested(&self, event: &Event)`:
+/// let event = &Event::Block(1);
+/// assert!(interested_in!(event, Event::Block | Event::Log));
+/// assert!(!interested_in!(event, Event::Tick));
+/// ```
+#[macro_export]
+macro_rules! interested_in {
+    ($event: expr, $($variant: path)|+) => {
+        matches!($event, $($variant(..))|+)
+    };
+}
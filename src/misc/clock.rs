@@ -0,0 +1,274 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::sync::{oneshot, watch};
+
+/// The time source of an engine: the system clock when running live, or a [`SimulatedClock`] driven by historical
+/// data when backtesting.
+#[derive(Debug, Clone, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    Simulated(SimulatedClock),
+}
+
+impl Clock {
+    pub fn now(&self) -> SystemTime {
+        match self {
+            Clock::System => SystemTime::now(),
+            Clock::Simulated(clock) => clock.now(),
+        }
+    }
+
+    pub async fn sleep(&self, duration: Duration) {
+        match self {
+            Clock::System => tokio::time::sleep(duration).await,
+            Clock::Simulated(clock) => clock.sleep(duration).await,
+        }
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        matches!(self, Clock::Simulated(_))
+    }
+}
+
+impl From<SimulatedClock> for Clock {
+    fn from(clock: SimulatedClock) -> Self {
+        Clock::Simulated(clock)
+    }
+}
+
+/// A clock that only moves when told to, e.g. by a historical collector as it emits blocks. Clones share the
+/// same time.
+///
+/// Whatever waits on the clock registers an [`Alarm`]. Advancing the clock hands a [`Wakeup`] to each alarm that
+/// became due, and [`settled`](Self::settled) waits until they have all been dropped. A historical collector settles
+/// the clock before emitting a block, so everything due at the block's timestamp, such as the ticks of a
+/// [`SimulatedIntervalCollector`](crate::collector::SimulatedIntervalCollector), reaches the engine before the
+/// block does.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: Mutex<State>,
+    /// The number of wakeups handed out and not dropped yet.
+    awake: watch::Sender<usize>,
+}
+
+#[derive(Debug)]
+struct State {
+    /// Milliseconds since the unix epoch.
+    now_ms: u64,
+    /// Alarms waiting for their deadline, keyed by deadline and registration order.
+    alarms: BTreeMap<(u64, u64), oneshot::Sender<Wakeup>>,
+    next_alarm: u64,
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new(UNIX_EPOCH)
+    }
+}
+
+impl SimulatedClock {
+    pub fn new(start: SystemTime) -> Self {
+        let (awake, _) = watch::channel(0);
+        Self {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    now_ms: to_ms(start),
+                    alarms: BTreeMap::new(),
+                    next_alarm: 0,
+                }),
+                awake,
+            }),
+        }
+    }
+
+    pub fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.inner.state.lock().unwrap().now_ms)
+    }
+
+    /// Move the clock forward to `time`, waking the alarms due by then in order. The clock never goes backwards, so
+    /// earlier times are ignored.
+    pub fn advance_to(&self, time: SystemTime) {
+        let time = to_ms(time);
+
+        let due = {
+            let mut state = self.inner.state.lock().unwrap();
+            if time <= state.now_ms {
+                return;
+            }
+            state.now_ms = time;
+
+            let later = state.alarms.split_off(&(time.saturating_add(1), 0));
+            std::mem::replace(&mut state.alarms, later)
+        };
+
+        for sender in due.into_values() {
+            // The alarm was dropped in the meantime, the wakeup with it.
+            let _ = sender.send(self.inner.wakeup());
+        }
+    }
+
+    pub fn advance_to_unix_timestamp(&self, timestamp: u64) {
+        self.advance_to(UNIX_EPOCH + Duration::from_secs(timestamp));
+    }
+
+    /// Wait until every [`Wakeup`] handed out so far has been dropped.
+    pub async fn settled(&self) {
+        let mut awake = self.inner.awake.subscribe();
+
+        // The sender lives as long as `self`, so this can't fail.
+        let _ = awake.wait_for(|awake| *awake == 0).await;
+    }
+
+    /// Register an alarm going off once the clock reaches `deadline`, right away if it already has. Advancing the
+    /// clock past `deadline` does not wait for the alarm to be polled, but [`settled`](Self::settled) does.
+    pub fn alarm(&self, deadline: SystemTime) -> Alarm {
+        let deadline = to_ms(deadline);
+        let (sender, receiver) = oneshot::channel();
+
+        let key = {
+            let mut state = self.inner.state.lock().unwrap();
+            let key = (deadline, state.next_alarm);
+            state.next_alarm += 1;
+
+            if deadline > state.now_ms {
+                state.alarms.insert(key, sender);
+            } else {
+                drop(state);
+                let _ = sender.send(self.inner.wakeup());
+            }
+            key
+        };
+
+        Alarm {
+            clock: self.inner.clone(),
+            key,
+            receiver,
+        }
+    }
+
+    /// Wait until the clock reaches `deadline`.
+    pub async fn sleep_until(&self, deadline: SystemTime) {
+        self.alarm(deadline).wait().await;
+    }
+
+    pub async fn sleep(&self, duration: Duration) {
+        self.sleep_until(self.now() + duration).await
+    }
+}
+
+impl Inner {
+    fn wakeup(self: &Arc<Self>) -> Wakeup {
+        self.awake.send_modify(|awake| *awake += 1);
+        Wakeup { clock: self.clone() }
+    }
+}
+
+/// A pending wakeup of a [`SimulatedClock`], see [`SimulatedClock::alarm`].
+#[derive(Debug)]
+pub struct Alarm {
+    clock: Arc<Inner>,
+    key: (u64, u64),
+    receiver: oneshot::Receiver<Wakeup>,
+}
+
+impl Alarm {
+    pub fn deadline(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.key.0)
+    }
+
+    /// Wait until the clock reaches the deadline. Hold the [`Wakeup`] until what the alarm triggers is done, e.g.
+    /// until an event is handed to the engine.
+    pub async fn wait(mut self) -> Wakeup {
+        // The alarm keeps the clock alive, and the clock only drops the sender after sending.
+        (&mut self.receiver).await.expect("alarm dropped by its clock")
+    }
+}
+
+impl Drop for Alarm {
+    fn drop(&mut self) {
+        self.clock.state.lock().unwrap().alarms.remove(&self.key);
+    }
+}
+
+/// Handed to an [`Alarm`] when it goes off. [`SimulatedClock::settled`] waits until it is dropped.
+#[derive(Debug)]
+pub struct Wakeup {
+    clock: Arc<Inner>,
+}
+
+impl Drop for Wakeup {
+    fn drop(&mut self) {
+        self.clock.awake.send_modify(|awake| *awake -= 1);
+    }
+}
+
+fn to_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::SimulatedClock;
+
+    #[tokio::test]
+    async fn test_simulated_sleep_follows_clock() {
+        let clock = SimulatedClock::default();
+
+        let sleeper = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep_until(UNIX_EPOCH + Duration::from_secs(10)).await }
+        });
+
+        clock.advance_to_unix_timestamp(5);
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+
+        clock.advance_to_unix_timestamp(3);
+        assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(5));
+
+        clock.advance_to_unix_timestamp(12);
+        sleeper.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_settled_waits_for_wakeups() {
+        let clock = SimulatedClock::default();
+        let alarm = clock.alarm(UNIX_EPOCH + Duration::from_secs(2));
+        let late = clock.alarm(UNIX_EPOCH + Duration::from_secs(3));
+
+        // Alarms that aren't due yet don't hold the clock back.
+        clock.advance_to_unix_timestamp(1);
+        clock.settled().await;
+
+        clock.advance_to_unix_timestamp(2);
+        let wakeup = alarm.wait().await;
+        let settled = tokio::time::timeout(Duration::from_millis(50), clock.settled()).await;
+        assert!(settled.is_err(), "settled while a wakeup is held");
+
+        drop(wakeup);
+        clock.settled().await;
+
+        // Dropping an alarm that went off releases its wakeup as well.
+        clock.advance_to_unix_timestamp(3);
+        drop(late);
+        clock.settled().await;
+
+        // An alarm for a time already passed goes off at once.
+        drop(clock.alarm(UNIX_EPOCH + Duration::from_secs(1)).wait().await);
+        clock.settled().await;
+    }
+}
@@ -0,0 +1,473 @@
+use std::{future::Future, time::Duration};
+
+use async_trait::async_trait;
+use eyre::Result;
+use futures::{StreamExt, future};
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::interface::{ICollector, collector::CollectorStream};
+
+/// Events of several collectors of the same type, in the order they arrive.
+///
+/// The merged stream ends once every collector's stream has ended, so with live collectors it is only restarted when
+/// all of them stopped; a collector that fails to subscribe fails the whole merge.
+pub struct CollectorMerge<E> {
+    inner: Vec<Box<dyn ICollector<E>>>,
+    name: String,
+}
+
+impl<E> CollectorMerge<E> {
+    pub fn new(collectors: Vec<Box<dyn ICollector<E>>>) -> Self {
+        let name = collectors.iter().map(|c| c.name()).collect::<Vec<_>>().join(" + ");
+        Self {
+            inner: collectors,
+            name,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for CollectorMerge<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.iter().all(|c| c.is_finite())
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        for collector in &self.inner {
+            if let Some(chain_id) = collector.chain_id().await {
+                return Some(chain_id);
+            }
+        }
+        None
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        let streams = future::try_join_all(self.inner.iter().map(|c| c.get_event_stream())).await?;
+        Ok(Box::pin(futures::stream::select_all(streams)))
+    }
+}
+
+/// Passes on at most one event per `interval`, the first one, and drops the others.
+pub struct CollectorThrottle<E> {
+    inner: Box<dyn ICollector<E>>,
+    interval: Duration,
+}
+
+impl<E> CollectorThrottle<E> {
+    pub fn new(collector: Box<dyn ICollector<E>>, interval: Duration) -> Self {
+        Self {
+            inner: collector,
+            interval,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for CollectorThrottle<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        let stream = self.inner.get_event_stream().await?;
+        let interval = self.interval;
+        let mut last_passed: Option<Instant> = None;
+
+        let stream = stream.filter(move |_| {
+            let now = Instant::now();
+            let pass = last_passed.is_none_or(|last| now.duration_since(last) >= interval);
+            if pass {
+                last_passed = Some(now);
+            }
+            future::ready(pass)
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Passes on the latest event at the end of every `interval` in which there was one, e.g. to act on the current
+/// price every second rather than on every tick.
+pub struct CollectorSample<E> {
+    inner: Box<dyn ICollector<E>>,
+    interval: Duration,
+}
+
+impl<E> CollectorSample<E> {
+    pub fn new(collector: Box<dyn ICollector<E>>, interval: Duration) -> Self {
+        Self {
+            inner: collector,
+            interval,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for CollectorSample<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        let mut stream = self.inner.get_event_stream().await?;
+        let mut ticker = tokio::time::interval_at(Instant::now() + self.interval, self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let stream = async_stream::stream! {
+            let mut latest = None;
+
+            loop {
+                let (sampled, ended) = tokio::select! {
+                    event = stream.next() => match event {
+                        Some(event) => {
+                            latest = Some(event);
+                            (None, false)
+                        }
+                        None => (latest.take(), true),
+                    },
+                    _ = ticker.tick() => (latest.take(), false),
+                };
+
+                if let Some(event) = sampled {
+                    yield event;
+                }
+                if ended {
+                    break;
+                }
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Passes on an event once no other followed it for `quiet`, e.g. to act once on a burst of pool updates. Only the
+/// last event of a burst is kept.
+pub struct CollectorDebounce<E> {
+    inner: Box<dyn ICollector<E>>,
+    quiet: Duration,
+}
+
+impl<E> CollectorDebounce<E> {
+    pub fn new(collector: Box<dyn ICollector<E>>, quiet: Duration) -> Self {
+        Self {
+            inner: collector,
+            quiet,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<E> for CollectorDebounce<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E>> {
+        let mut stream = self.inner.get_event_stream().await?;
+        let quiet = self.quiet;
+
+        let stream = async_stream::stream! {
+            let mut pending = None;
+
+            loop {
+                // `None` when the burst went quiet.
+                let next = if pending.is_some() {
+                    tokio::select! {
+                        event = stream.next() => Some(event),
+                        _ = tokio::time::sleep(quiet) => None,
+                    }
+                } else {
+                    Some(stream.next().await)
+                };
+
+                match next {
+                    Some(Some(event)) => pending = Some(event),
+                    Some(None) => {
+                        if let Some(event) = pending.take() {
+                            yield event;
+                        }
+                        break;
+                    }
+                    None => {
+                        if let Some(event) = pending.take() {
+                            yield event;
+                        }
+                    }
+                }
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Groups events into batches of up to `max_size`, passing on a smaller batch once `max_wait` has passed since its
+/// first event, e.g. to handle all logs of a block at once.
+pub struct CollectorBatch<E> {
+    inner: Box<dyn ICollector<E>>,
+    max_size: usize,
+    max_wait: Duration,
+}
+
+impl<E> CollectorBatch<E> {
+    pub fn new(collector: Box<dyn ICollector<E>>, max_size: usize, max_wait: Duration) -> Self {
+        Self {
+            inner: collector,
+            max_size: max_size.max(1),
+            max_wait,
+        }
+    }
+}
+
+#[async_trait]
+impl<E> ICollector<Vec<E>> for CollectorBatch<E>
+where
+    E: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, Vec<E>>> {
+        let mut stream = self.inner.get_event_stream().await?;
+        let max_size = self.max_size;
+        let max_wait = self.max_wait;
+
+        let stream = async_stream::stream! {
+            let mut batch = Vec::new();
+            let mut deadline = None;
+
+            loop {
+                // `None` when the batch is due.
+                let next = match deadline {
+                    Some(deadline) => tokio::select! {
+                        event = stream.next() => Some(event),
+                        _ = tokio::time::sleep_until(deadline) => None,
+                    },
+                    None => Some(stream.next().await),
+                };
+
+                match next {
+                    Some(Some(event)) => {
+                        if batch.is_empty() {
+                            deadline = Some(Instant::now() + max_wait);
+                        }
+                        batch.push(event);
+
+                        if batch.len() >= max_size {
+                            deadline = None;
+                            yield std::mem::take(&mut batch);
+                        }
+                    }
+                    Some(None) => {
+                        if !batch.is_empty() {
+                            yield batch;
+                        }
+                        break;
+                    }
+                    None => {
+                        deadline = None;
+                        yield std::mem::take(&mut batch);
+                    }
+                }
+            }
+        };
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Like [`CollectorFilterMap`](crate::CollectorFilterMap), but `f` is async, so it can enrich events with provider
+/// calls, e.g. fetch the receipt of each log. Up to `concurrency` calls run at once; events keep their order.
+///
+/// ```ignore
+/// let receipts = CollectorAsyncFilterMap::new(Box::new(log_collector), move |log: Log| {
+///     let provider = provider.clone();
+///     async move { provider.get_transaction_receipt(log.transaction_hash?).await.ok()? }
+/// })
+/// .with_concurrency(8);
+/// ```
+pub struct CollectorAsyncFilterMap<E, F> {
+    inner: Box<dyn ICollector<E>>,
+    f: F,
+    concurrency: usize,
+}
+
+impl<E, F> CollectorAsyncFilterMap<E, F> {
+    pub fn new(collector: Box<dyn ICollector<E>>, f: F) -> Self {
+        Self {
+            inner: collector,
+            f,
+            concurrency: 1,
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+#[async_trait]
+impl<E1, E2, F, Fut> ICollector<E2> for CollectorAsyncFilterMap<E1, F>
+where
+    E1: Send + Sync + 'static,
+    E2: Send + Sync + 'static,
+    F: Fn(E1) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Option<E2>> + Send + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, E2>> {
+        let stream = self.inner.get_event_stream().await?;
+        let stream = stream
+            .map(|event| (self.f)(event))
+            .buffered(self.concurrency)
+            .filter_map(future::ready);
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use tokio::time::Instant;
+
+    use super::{CollectorAsyncFilterMap, CollectorBatch, CollectorDebounce, CollectorSample, CollectorThrottle};
+    use crate::{ICollector, batch_collector, map_collector, merge_collectors};
+
+    /// Emits each value at its offset in milliseconds from subscribing.
+    struct TimedCollector(Vec<(u64, u64)>);
+
+    #[async_trait::async_trait]
+    impl ICollector<u64> for TimedCollector {
+        fn name(&self) -> &str {
+            "timed"
+        }
+
+        async fn get_event_stream(&self) -> eyre::Result<crate::CollectorStream<'_, u64>> {
+            let start = Instant::now();
+            let stream = futures::stream::iter(self.0.clone()).then(move |(offset, value)| async move {
+                tokio::time::sleep_until(start + Duration::from_millis(offset)).await;
+                value
+            });
+            Ok(Box::pin(stream))
+        }
+    }
+
+    fn every_10ms(count: u64) -> TimedCollector {
+        TimedCollector((0..count).map(|i| (i * 10, i)).collect())
+    }
+
+    async fn collect<E>(collector: impl ICollector<E>) -> Vec<E> {
+        collector.get_event_stream().await.unwrap().collect().await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_merge() {
+        let merged = merge_collectors!(
+            TimedCollector(vec![(0, 1), (20, 3)]),
+            TimedCollector(vec![(10, 2), (30, 4)])
+        );
+        assert_eq!(merged.name(), "timed + timed");
+        assert_eq!(collect(merged).await, vec![1u64, 2, 3, 4]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_and_sample() {
+        let throttled = CollectorThrottle::new(Box::new(every_10ms(11)), Duration::from_millis(35));
+        assert_eq!(collect(throttled).await, vec![0, 4, 8]);
+
+        // The last event is passed on when the stream ends, without waiting for the next tick.
+        let sampled = CollectorSample::new(Box::new(every_10ms(11)), Duration::from_millis(33));
+        assert_eq!(collect(sampled).await, vec![3, 6, 9, 10]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_debounce() {
+        let bursts = TimedCollector(vec![(0, 1), (10, 2), (20, 3), (200, 4), (210, 5)]);
+        let debounced = CollectorDebounce::new(Box::new(bursts), Duration::from_millis(50));
+        assert_eq!(collect(debounced).await, vec![3, 5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch() {
+        let events = TimedCollector(vec![(0, 0), (10, 1), (20, 2), (30, 3), (200, 4)]);
+        let batched = CollectorBatch::new(Box::new(events), 3, Duration::from_millis(50));
+        assert_eq!(collect(batched).await, vec![vec![0, 1, 2], vec![3], vec![4]]);
+
+        #[derive(Debug, PartialEq)]
+        struct Batch(Vec<u64>);
+
+        let wrapped = map_collector!(batch_collector!(every_10ms(3), 2, Duration::from_secs(1)), Batch);
+        assert_eq!(collect(wrapped).await, vec![Batch(vec![0, 1]), Batch(vec![2])]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_async_filter_map_keeps_order() {
+        let enriched = CollectorAsyncFilterMap::new(Box::new(every_10ms(6)), |value: u64| async move {
+            // Later events resolve first.
+            tokio::time::sleep(Duration::from_millis(100 - value * 10)).await;
+            value.is_multiple_of(2).then_some(value * 10)
+        })
+        .with_concurrency(6);
+        assert_eq!(collect(enriched).await, vec![0, 20, 40]);
+    }
+}
@@ -257,7 +257,7 @@ impl Logger {
 
             let first_line_padded = format!(
                 "{:width$}",
-                wrapped_lines.get(0).unwrap_or(&String::new()),
+                wrapped_lines.first().unwrap_or(&String::new()),
                 width = value_width
             );
             let first_line = format!(" {} │ {} ", key_padded, first_line_padded);
@@ -270,8 +270,8 @@ impl Logger {
             ));
             table_lines.push(format!("│{}│", first_line));
 
-            for i in 1..wrapped_lines.len() {
-                let line_padded = format!("{:width$}", wrapped_lines[i], width = value_width);
+            for wrapped_line in wrapped_lines.iter().skip(1) {
+                let line_padded = format!("{:width$}", wrapped_line, width = value_width);
                 let content_line = format!(" {} │ {} ", " ".repeat(max_key_length), line_padded);
                 console_table_lines.push(format!(
                     "{}│{}{}{}│{}",
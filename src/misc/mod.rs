@@ -1,7 +1,15 @@
+pub mod clock;
+pub mod combinators;
 pub mod logger;
+pub mod strategy_group;
 pub mod types;
 pub mod utils;
 
+pub use clock::{Alarm, Clock, SimulatedClock, Wakeup};
+pub use combinators::{
+    CollectorAsyncFilterMap, CollectorBatch, CollectorDebounce, CollectorMerge, CollectorSample, CollectorThrottle,
+};
 pub use logger::*;
-pub use types::{CollectorFilterMap, CollectorMap, ExecutorMap};
+pub use strategy_group::StrategyGroup;
+pub use types::{CollectorFilterMap, CollectorMap, ExecutorMap, StrategyMap};
 pub use utils::*;
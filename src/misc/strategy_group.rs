@@ -0,0 +1,192 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use eyre::{Context, Result, bail, eyre};
+
+use crate::{
+    IActionSubmitter, IStrategy,
+    engine::{EngineEvent, Envelope, ExecutionReport, ShardKey},
+};
+
+/// Runs several strategies as one, with state they share, e.g. a pool tracker and the arbitrage strategies reading
+/// its reserves.
+///
+/// Members see each event in the order they were added, so what a member writes to the state while processing an
+/// event is visible to the members after it. Engine events and execution reports go to every member; a member can
+/// tell its own actions apart by their [`ActionId`](crate::ActionId). The group's snapshot holds the snapshot of
+/// each member under the member's name, numbered from the second member of the same name on.
+///
+/// ```ignore
+/// let group = StrategyGroup::new("arbitrage", Reserves::default())
+///     .with_strategy(|reserves| Box::new(ReserveTracker::new(reserves)))
+///     .with_strategy(|reserves| Box::new(TriangularArb::new(reserves)));
+/// engine.add_strategy(Box::new(group));
+/// ```
+pub struct StrategyGroup<E, A, S> {
+    name: String,
+    state: Arc<RwLock<S>>,
+    members: Vec<Box<dyn IStrategy<E, A>>>,
+}
+
+impl<E, A, S> StrategyGroup<E, A, S> {
+    pub fn new(name: impl Into<String>, state: S) -> Self {
+        Self {
+            name: name.into(),
+            state: Arc::new(RwLock::new(state)),
+            members: Vec::new(),
+        }
+    }
+
+    /// Add the strategy built by `factory` from the shared state.
+    pub fn with_strategy<F>(mut self, factory: F) -> Self
+    where
+        F: FnOnce(Arc<RwLock<S>>) -> Box<dyn IStrategy<E, A>>,
+    {
+        self.members.push(factory(self.state.clone()));
+        self
+    }
+
+    pub fn state(&self) -> Arc<RwLock<S>> {
+        self.state.clone()
+    }
+
+    /// The key of each member's snapshot: its name, followed by `#2`, `#3`... for members sharing a name.
+    fn member_keys(&self) -> Vec<String>
+    where
+        E: Send + Sync + Clone + 'static,
+        A: Send + Sync + Clone + 'static,
+    {
+        let mut seen = HashMap::<&str, usize>::new();
+
+        self.members
+            .iter()
+            .map(|member| {
+                let count = seen.entry(member.name()).or_default();
+                *count += 1;
+
+                match *count {
+                    1 => member.name().to_string(),
+                    n => format!("{}#{n}", member.name()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<E, A, S> IStrategy<E, A> for StrategyGroup<E, A, S>
+where
+    E: Send + Sync + Clone + 'static,
+    A: Send + Sync + Clone + 'static,
+    S: Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn sync_state(&mut self, submitter: Arc<dyn IActionSubmitter<A>>) -> Result<()> {
+        for member in &mut self.members {
+            member.sync_state(submitter.clone()).await?;
+        }
+        Ok(())
+    }
+
+    /// The members' snapshots, each as its key and state prefixed with their lengths. `None` if no member takes
+    /// snapshots.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        let mut bytes = Vec::new();
+
+        for (key, member) in self.member_keys().into_iter().zip(&self.members) {
+            let Some(state) = member.snapshot() else {
+                continue;
+            };
+
+            for field in [key.as_bytes(), &state] {
+                bytes.extend_from_slice(&(field.len() as u64).to_le_bytes());
+                bytes.extend_from_slice(field);
+            }
+        }
+
+        (!bytes.is_empty()).then_some(bytes)
+    }
+
+    /// Restore each member found in `snapshot`; members without a state in it are left as they are.
+    fn restore(&mut self, mut snapshot: &[u8]) -> Result<()> {
+        let mut states = HashMap::new();
+
+        while !snapshot.is_empty() {
+            let key = take_field(&mut snapshot)?;
+            let state = take_field(&mut snapshot)?;
+            states.insert(String::from_utf8_lossy(key).into_owned(), state);
+        }
+
+        for (key, member) in self.member_keys().into_iter().zip(&mut self.members) {
+            if let Some(state) = states.get(&key) {
+                member
+                    .restore(state)
+                    .wrap_err_with(|| format!("fail to restore group member {key}"))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_interested(&self, event: &E) -> bool {
+        self.members.iter().any(|member| member.is_interested(event))
+    }
+
+    /// The shard key of the first member interested in `event` that has one.
+    fn shard_key(&self, event: &E) -> Option<ShardKey> {
+        self.members
+            .iter()
+            .filter(|member| member.is_interested(event))
+            .find_map(|member| member.shard_key(event))
+    }
+
+    async fn process_envelope(&mut self, envelope: Envelope<E>, submitter: Arc<dyn IActionSubmitter<A>>) {
+        for member in &mut self.members {
+            if member.is_interested(&envelope.event) {
+                member.process_envelope(envelope.clone(), submitter.clone()).await;
+            }
+        }
+    }
+
+    async fn process_event(&mut self, event: E, submitter: Arc<dyn IActionSubmitter<A>>) {
+        for member in &mut self.members {
+            if member.is_interested(&event) {
+                member.process_event(event.clone(), submitter.clone()).await;
+            }
+        }
+    }
+
+    async fn on_engine_event(&mut self, event: EngineEvent, submitter: Arc<dyn IActionSubmitter<A>>) {
+        for member in &mut self.members {
+            member.on_engine_event(event.clone(), submitter.clone()).await;
+        }
+    }
+
+    async fn on_execution_report(&mut self, report: ExecutionReport, submitter: Arc<dyn IActionSubmitter<A>>) {
+        for member in &mut self.members {
+            member.on_execution_report(report.clone(), submitter.clone()).await;
+        }
+    }
+}
+
+/// Split a length-prefixed field off the front of `bytes`.
+fn take_field<'a>(bytes: &mut &'a [u8]) -> Result<&'a [u8]> {
+    let (len, rest) = bytes
+        .split_first_chunk::<8>()
+        .ok_or_else(|| eyre!("truncated group snapshot"))?;
+    let len = usize::try_from(u64::from_le_bytes(*len))?;
+
+    if rest.len() < len {
+        bail!("truncated group snapshot");
+    }
+
+    let (field, rest) = rest.split_at(len);
+    *bytes = rest;
+    Ok(field)
+}
@@ -1,8 +1,14 @@
+use std::{marker::PhantomData, sync::Arc};
+
 use async_trait::async_trait;
 use eyre::Result;
 use futures::StreamExt;
 
-use crate::interface::{ICollector, IExecutor, collector::CollectorStream};
+use crate::{
+    ActionId, ExecutionOutcome, IActionSubmitter, IStrategy,
+    engine::{EngineEvent, Envelope, ExecutionReport, ShardKey},
+    interface::{ICollector, IExecutor, collector::CollectorStream},
+};
 
 pub struct CollectorMap<E, F> {
     inner: Box<dyn ICollector<E>>,
@@ -26,6 +32,14 @@ where
         self.inner.name()
     }
 
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, E2>> {
         let stream = self.inner.get_event_stream().await?;
         let f = self.f.clone();
@@ -50,33 +64,60 @@ impl<E1, E2, F> ICollector<E2> for CollectorFilterMap<E1, F>
 where
     E1: Send + Sync + 'static,
     E2: Send + Sync + 'static,
-    F: Fn(E1) -> Option<E2> + Send + Sync + Clone + Copy + 'static,
+    F: Fn(E1) -> Option<E2> + Send + Sync + Clone + 'static,
 {
     fn name(&self) -> &str {
         self.inner.name()
     }
 
+    fn is_finite(&self) -> bool {
+        self.inner.is_finite()
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        self.inner.chain_id().await
+    }
+
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, E2>> {
         let stream = self.inner.get_event_stream().await?;
-        let f = self.f;
-        let stream = stream.filter_map(move |v| async move { f(v) });
+        let f = self.f.clone();
+        let stream = stream.filter_map(move |v| futures::future::ready(f(v)));
         Ok(Box::pin(stream))
     }
 }
 
-pub struct ExecutorMap<A, F> {
-    inner: Box<dyn IExecutor<A>>,
+pub struct ExecutorMap<A1, A2, F> {
+    inner: Box<dyn IExecutor<A2>>,
     f: F,
+    /// `f` by reference, to tell the actions the executor is interested in without mapping them.
+    f_ref: Option<RefMapper<A1, A2>>,
 }
 
-impl<A, F> ExecutorMap<A, F> {
-    pub fn new(executor: Box<dyn IExecutor<A>>, f: F) -> Self {
-        Self { inner: executor, f }
+type RefMapper<A1, A2> = Box<dyn Fn(&A1) -> Option<&A2> + Send + Sync>;
+
+impl<A1, A2, F> ExecutorMap<A1, A2, F> {
+    pub fn new(executor: Box<dyn IExecutor<A2>>, f: F) -> Self
+    where
+        F: Fn(A1) -> Option<A2>,
+    {
+        Self {
+            inner: executor,
+            f,
+            f_ref: None,
+        }
+    }
+
+    /// Map actions by reference with `f_ref`, which must pick the same actions as `f`, so that the executor is only
+    /// interested in those. Without it, the executor is interested in every action, and while it is quarantined the
+    /// actions `f` would skip are failed too.
+    pub fn with_ref(mut self, f_ref: impl Fn(&A1) -> Option<&A2> + Send + Sync + 'static) -> Self {
+        self.f_ref = Some(Box::new(f_ref));
+        self
     }
 }
 
 #[async_trait]
-impl<A1, A2, F> IExecutor<A1> for ExecutorMap<A2, F>
+impl<A1, A2, F> IExecutor<A1> for ExecutorMap<A1, A2, F>
 where
     A1: Send + Sync + 'static,
     A2: Send + Sync + 'static,
@@ -93,4 +134,147 @@ where
             None => Ok(()),
         }
     }
+
+    fn is_interested(&self, action: &A1) -> bool {
+        match &self.f_ref {
+            Some(f_ref) => f_ref(action).is_some_and(|action| self.inner.is_interested(action)),
+            None => true,
+        }
+    }
+
+    async fn execute_with_outcome(&self, action: A1) -> ExecutionOutcome {
+        match (self.f)(action) {
+            Some(action) => self.inner.execute_with_outcome(action).await,
+            None => ExecutionOutcome::Skipped,
+        }
+    }
+}
+
+/// Runs a strategy written for other event and action types, e.g. a reusable alert strategy in an engine with its
+/// own `Event` and `Action` enums. `project` picks the strategy's events out of the engine's, and `lift` turns the
+/// strategy's actions into the engine's; see [`map_strategy!`](crate::map_strategy).
+pub struct StrategyMap<E1, A1, E2, FE, FA> {
+    inner: Box<dyn IStrategy<E1, A1>>,
+    project: FE,
+    lift: Arc<FA>,
+    _phantom: PhantomData<fn(&E2)>,
+}
+
+impl<E1, A1, E2, FE, FA> StrategyMap<E1, A1, E2, FE, FA>
+where
+    E1: Send + Sync + Clone + 'static,
+    A1: Send + Sync + Clone + 'static,
+{
+    pub fn new<A2>(strategy: Box<dyn IStrategy<E1, A1>>, project: FE, lift: FA) -> Self
+    where
+        FE: Fn(&E2) -> Option<&E1>,
+        FA: Fn(A1) -> A2,
+    {
+        Self {
+            inner: strategy,
+            project,
+            lift: Arc::new(lift),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<E1, A1, E2, FE, FA> StrategyMap<E1, A1, E2, FE, FA> {
+    fn lift<A2>(&self, submitter: Arc<dyn IActionSubmitter<A2>>) -> Arc<dyn IActionSubmitter<A1>>
+    where
+        A1: Send + Sync + Clone + 'static,
+        A2: Send + Sync + Clone + 'static,
+        FA: Fn(A1) -> A2 + Send + Sync + 'static,
+    {
+        Arc::new(LiftedSubmitter {
+            submitter,
+            lift: self.lift.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl<E1, A1, E2, A2, FE, FA> IStrategy<E2, A2> for StrategyMap<E1, A1, E2, FE, FA>
+where
+    E1: Send + Sync + Clone + 'static,
+    A1: Send + Sync + Clone + 'static,
+    E2: Send + Sync + Clone + 'static,
+    A2: Send + Sync + Clone + 'static,
+    FE: Fn(&E2) -> Option<&E1> + Send + Sync + 'static,
+    FA: Fn(A1) -> A2 + Send + Sync + 'static,
+{
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn sync_state(&mut self, submitter: Arc<dyn IActionSubmitter<A2>>) -> Result<()> {
+        let submitter = self.lift(submitter);
+        self.inner.sync_state(submitter).await
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        self.inner.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> Result<()> {
+        self.inner.restore(snapshot)
+    }
+
+    fn is_interested(&self, event: &E2) -> bool {
+        (self.project)(event).is_some_and(|event| self.inner.is_interested(event))
+    }
+
+    fn shard_key(&self, event: &E2) -> Option<ShardKey> {
+        (self.project)(event).and_then(|event| self.inner.shard_key(event))
+    }
+
+    async fn process_event(&mut self, event: E2, submitter: Arc<dyn IActionSubmitter<A2>>) {
+        let Some(event) = (self.project)(&event).cloned() else {
+            return;
+        };
+        let submitter = self.lift(submitter);
+        self.inner.process_event(event, submitter).await
+    }
+
+    async fn process_envelope(&mut self, envelope: Envelope<E2>, submitter: Arc<dyn IActionSubmitter<A2>>) {
+        let Some(event) = (self.project)(&envelope.event).cloned() else {
+            return;
+        };
+        let envelope = Envelope {
+            meta: envelope.meta,
+            event,
+        };
+        let submitter = self.lift(submitter);
+        self.inner.process_envelope(envelope, submitter).await
+    }
+
+    async fn on_engine_event(&mut self, event: EngineEvent, submitter: Arc<dyn IActionSubmitter<A2>>) {
+        let submitter = self.lift(submitter);
+        self.inner.on_engine_event(event, submitter).await
+    }
+
+    async fn on_execution_report(&mut self, report: ExecutionReport, submitter: Arc<dyn IActionSubmitter<A2>>) {
+        let submitter = self.lift(submitter);
+        self.inner.on_execution_report(report, submitter).await
+    }
+}
+
+struct LiftedSubmitter<A, F> {
+    submitter: Arc<dyn IActionSubmitter<A>>,
+    lift: Arc<F>,
+}
+
+impl<A1, A2, F> IActionSubmitter<A1> for LiftedSubmitter<A2, F>
+where
+    A1: Send + Sync + Clone + 'static,
+    A2: Send + Sync + Clone + 'static,
+    F: Fn(A1) -> A2 + Send + Sync + 'static,
+{
+    fn submit(&self, action: A1) {
+        self.submitter.submit((self.lift)(action));
+    }
+
+    fn submit_tracked(&self, action: A1) -> Option<ActionId> {
+        self.submitter.submit_tracked((self.lift)(action))
+    }
 }
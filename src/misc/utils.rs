@@ -20,8 +20,8 @@ pub fn calculate_pair_address(token0: Address, token1: Address, factory: Address
 
     // Pack tokens for hashing (equivalent to abi.encodePacked)
     let mut packed = Vec::with_capacity(40);
-    packed.extend_from_slice(&token0.as_slice());
-    packed.extend_from_slice(&token1.as_slice());
+    packed.extend_from_slice(token0.as_slice());
+    packed.extend_from_slice(token1.as_slice());
 
     // Calculate salt
     let salt = keccak256(&packed);
@@ -29,8 +29,8 @@ pub fn calculate_pair_address(token0: Address, token1: Address, factory: Address
     // Pack data for final hash (prefix + factory + salt + init_code_hash)
     let mut final_data = Vec::with_capacity(85);
     final_data.push(0xff); // prefix for CREATE2
-    final_data.extend_from_slice(&factory.as_slice());
-    final_data.extend_from_slice(&salt.to_vec());
+    final_data.extend_from_slice(factory.as_slice());
+    final_data.extend_from_slice(salt.as_ref());
     final_data.extend_from_slice(&init_code_hash);
 
     // Calculate final hash and convert to address
@@ -32,7 +32,7 @@ pub fn parse_1167_bytecode(bytecode: &str) -> Result<String, Eip1167Error> {
     // push1 ... push20 use opcodes 0x60 ... 0x73
     let address_length = (push_opcode as i32) - 0x5f;
 
-    if address_length < 1 || address_length > 20 {
+    if !(1..=20).contains(&address_length) {
         return Err(Eip1167Error("Not an EIP-1167 bytecode".to_string()));
     }
 
@@ -21,7 +21,7 @@ pub fn read_string(hex: &str) -> Result<String, ReadStringError> {
         return Ok(String::new());
     }
 
-    if clean_hex.len() % 2 != 0 {
+    if !clean_hex.len().is_multiple_of(2) {
         return Err(ReadStringError("Invalid hex string length".to_string()));
     }
 
@@ -155,11 +155,11 @@ v4-periphery/=lib/v4-periphery/
             let target_path = self.determine_file_location(file_path);
 
             // Create parent directories if needed
-            if let Some(parent) = target_path.parent() {
-                if !parent.exists() {
-                    fs::create_dir_all(parent)
-                        .with_context(|| format!("Failed to create parent directory: {:?}", parent))?;
-                }
+            if let Some(parent) = target_path.parent()
+                && !parent.exists()
+            {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create parent directory: {:?}", parent))?;
             }
 
             // Write the file
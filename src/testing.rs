@@ -0,0 +1,247 @@
+//! Unit-test strategies without collectors, executors or a network.
+//!
+//! A [`StrategyHarness`] feeds scripted events, engine events and execution reports to a strategy the way the
+//! engine would, and records every action it submits through a [`RecordingSubmitter`]:
+//!
+//! ```ignore
+//! #[tokio::test(start_paused = true)]
+//! async fn test_backruns_large_swaps() {
+//!     let mut harness = StrategyHarness::new(BackrunStrategy::default());
+//!     harness.sync_state().await.unwrap();
+//!
+//!     harness.push(Event::Transaction(large_swap())).await;
+//!     assert_eq!(harness.take_actions(), vec![Action::Backrun(large_swap_hash())]);
+//!
+//!     // Fires the strategy's timers, e.g. to expire a pending opportunity.
+//!     harness.advance(Duration::from_secs(12)).await;
+//!     assert!(harness.strategy().pending().is_empty());
+//! }
+//! ```
+//!
+//! Timers are driven by tokio's paused clock: run tests with `#[tokio::test(start_paused = true)]`, which needs
+//! tokio's `test-util` feature, and [`StrategyHarness::advance`] moves time forward without waiting. The
+//! harness's [`SimulatedClock`] follows along, for strategies given an engine [`Clock`].
+
+use std::{
+    marker::PhantomData,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    ActionId, Clock, IActionSubmitter, IStrategy, SimulatedClock,
+    engine::{EngineEvent, Envelope, EventMeta, ExecutionReport, trace::ActionOrigin},
+};
+
+/// An [`IActionSubmitter`] that keeps every submitted action, in order. Clones share the recorded actions.
+pub struct RecordingSubmitter<A> {
+    actions: Arc<Mutex<Vec<(ActionId, A)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<A> Clone for RecordingSubmitter<A> {
+    fn clone(&self) -> Self {
+        Self {
+            actions: self.actions.clone(),
+            next_id: self.next_id.clone(),
+        }
+    }
+}
+
+impl<A> Default for RecordingSubmitter<A> {
+    fn default() -> Self {
+        Self {
+            actions: Arc::new(Mutex::new(vec![])),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<A: Clone> RecordingSubmitter<A> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every action submitted so far.
+    pub fn actions(&self) -> Vec<A> {
+        self.actions.lock().unwrap().iter().map(|(_, a)| a.clone()).collect()
+    }
+
+    /// Every action submitted so far, along with the id [`IActionSubmitter::submit_tracked`] returned for it.
+    pub fn tracked_actions(&self) -> Vec<(ActionId, A)> {
+        self.actions.lock().unwrap().clone()
+    }
+
+    /// Remove and return the actions submitted so far, to assert on what the next event submits.
+    pub fn take(&self) -> Vec<A> {
+        self.actions.lock().unwrap().drain(..).map(|(_, a)| a).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<A> IActionSubmitter<A> for RecordingSubmitter<A>
+where
+    A: Send + Sync + Clone + 'static,
+{
+    fn submit(&self, action: A) {
+        self.submit_tracked(action);
+    }
+
+    fn submit_tracked(&self, action: A) -> Option<ActionId> {
+        let id = ActionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.actions.lock().unwrap().push((id, action));
+        Some(id)
+    }
+}
+
+/// Drives a single strategy the way the engine would, with everything it submits recorded.
+///
+/// Events wrapped by [`push`](Self::push) get [`EventMeta`] as if emitted by a collector named
+/// [`with_collector_name`](Self::with_collector_name), stamped with the harness's simulated clock. Like in the engine,
+/// events the strategy isn't [interested](IStrategy::is_interested) in are not delivered.
+pub struct StrategyHarness<S, E, A> {
+    strategy: S,
+    submitter: RecordingSubmitter<A>,
+    clock: SimulatedClock,
+    collector: Arc<str>,
+    chain_id: Option<u64>,
+    sequence: u64,
+    _event: PhantomData<fn(E)>,
+}
+
+impl<S, E, A> StrategyHarness<S, E, A>
+where
+    S: IStrategy<E, A>,
+    E: Send + Sync + Clone + 'static,
+    A: Send + Sync + Clone + 'static,
+{
+    pub fn new(strategy: S) -> Self {
+        Self {
+            strategy,
+            submitter: RecordingSubmitter::new(),
+            clock: SimulatedClock::new(UNIX_EPOCH),
+            collector: Arc::from("testing"),
+            chain_id: None,
+            sequence: 0,
+            _event: PhantomData,
+        }
+    }
+
+    /// Start the simulated clock at `time` instead of the unix epoch.
+    pub fn with_start_time(mut self, time: SystemTime) -> Self {
+        self.clock = SimulatedClock::new(time);
+        self
+    }
+
+    pub fn with_collector_name(mut self, name: &str) -> Self {
+        self.collector = Arc::from(name);
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    pub fn strategy_mut(&mut self) -> &mut S {
+        &mut self.strategy
+    }
+
+    pub fn submitter(&self) -> &RecordingSubmitter<A> {
+        &self.submitter
+    }
+
+    /// The simulated clock stamped on events, to hand to strategies that take an engine [`Clock`].
+    pub fn clock(&self) -> Clock {
+        self.clock.clone().into()
+    }
+
+    pub async fn sync_state(&mut self) -> eyre::Result<()> {
+        let submitter = self.submitter_arc();
+        self.strategy.sync_state(submitter).await
+    }
+
+    /// Deliver `event`, returning whether the strategy was interested in it.
+    pub async fn push(&mut self, event: E) -> bool {
+        let meta = EventMeta {
+            collector: self.collector.clone(),
+            chain_id: self.chain_id,
+            received_at: Instant::now(),
+            received_time: self.clock.now(),
+            sequence: self.sequence,
+        };
+        self.sequence += 1;
+
+        self.push_envelope(Envelope { meta, event }).await
+    }
+
+    /// Deliver `events` in order.
+    pub async fn push_all(&mut self, events: impl IntoIterator<Item = E>) {
+        for event in events {
+            self.push(event).await;
+        }
+    }
+
+    /// Deliver an event with hand-made metadata, e.g. an old `received_at` to test staleness checks.
+    pub async fn push_envelope(&mut self, envelope: Envelope<E>) -> bool {
+        if !self.strategy.is_interested(&envelope.event) {
+            return false;
+        }
+
+        let origin = ActionOrigin {
+            event: envelope.meta.clone(),
+            strategy: Arc::from(self.strategy.name()),
+            processing_started_at: Instant::now(),
+        };
+        let submitter = self.submitter_arc();
+
+        ActionOrigin::scope(Some(origin), self.strategy.process_envelope(envelope, submitter)).await;
+        true
+    }
+
+    pub async fn push_engine_event(&mut self, event: EngineEvent) {
+        let submitter = self.submitter_arc();
+        self.strategy.on_engine_event(event, submitter).await
+    }
+
+    pub async fn push_report(&mut self, report: ExecutionReport) {
+        let submitter = self.submitter_arc();
+        self.strategy.on_execution_report(report, submitter).await
+    }
+
+    /// Move tokio's paused clock, and the simulated clock with it, forward by `duration`, firing every timer due in
+    /// between. Without a paused clock this really waits.
+    pub async fn advance(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+        self.clock.advance_to(self.clock.now() + duration);
+        self.clock.settled().await;
+    }
+
+    /// Every action submitted so far.
+    pub fn actions(&self) -> Vec<A> {
+        self.submitter.actions()
+    }
+
+    /// Remove and return the actions submitted so far.
+    pub fn take_actions(&self) -> Vec<A> {
+        self.submitter.take()
+    }
+
+    fn submitter_arc(&self) -> Arc<dyn IActionSubmitter<A>> {
+        Arc::new(self.submitter.clone())
+    }
+}
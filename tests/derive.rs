@@ -0,0 +1,105 @@
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::Stream;
+use harpoon::{
+    Engine, HarpoonAction, HarpoonEvent, IActionSubmitter, ICollector, IExecutor, IStrategy, IVariant, async_trait,
+};
+
+type EventStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
+
+#[derive(Debug, Clone, PartialEq, HarpoonEvent)]
+enum Event {
+    Block(u64),
+    Message(String),
+    Tick,
+}
+
+#[derive(Debug, Clone, PartialEq, HarpoonAction)]
+enum Action {
+    SendTx(u64),
+    Cancel(u64),
+    Message(String),
+}
+
+#[test]
+fn test_derive_generates_conversions_and_extractors() {
+    assert_eq!(Event::from(7), Event::Block(7));
+    assert_eq!(Event::from("hi".to_string()), Event::Message("hi".to_string()));
+    assert_eq!(Event::Block(7).as_block(), Some(&7));
+    assert_eq!(Event::Tick.as_block(), None);
+    assert_eq!(Event::Message("hi".to_string()).into_message(), Some("hi".to_string()));
+
+    // `SendTx` and `Cancel` both hold a `u64`, so there is no `From<u64>` to pick between them.
+    assert_eq!(Action::from("hi".to_string()), Action::Message("hi".to_string()));
+    assert_eq!(Action::Cancel(1).into_send_tx(), None);
+
+    assert_eq!(<action::SendTx as IVariant<Action>>::wrap(1), Action::SendTx(1));
+    assert_eq!(
+        <action::Cancel as IVariant<Action>>::extract(Action::Cancel(2)),
+        Some(2)
+    );
+    assert_eq!(
+        <action::Cancel as IVariant<Action>>::extract_ref(&Action::SendTx(2)),
+        None
+    );
+}
+
+struct FiniteCollector(Vec<u64>);
+
+#[async_trait]
+impl ICollector<u64> for FiniteCollector {
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        Ok(Box::pin(futures::stream::iter(self.0.clone())))
+    }
+}
+
+/// Sends a transaction for every block, knowing nothing of the engine's enums.
+struct BlockStrategy;
+
+#[async_trait]
+impl IStrategy<u64, u64> for BlockStrategy {
+    async fn process_event(&mut self, block: u64, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        submitter.submit(block * 10);
+    }
+}
+
+#[derive(Default)]
+struct RecordingExecutor {
+    executed: Arc<Mutex<Vec<u64>>>,
+}
+
+#[async_trait]
+impl IExecutor<u64> for RecordingExecutor {
+    async fn execute(&self, action: u64) -> eyre::Result<()> {
+        self.executed.lock().unwrap().push(action);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_engine_registers_components_for_variants() {
+    let sender = RecordingExecutor::default();
+    let sent = sender.executed.clone();
+    let canceller = RecordingExecutor::default();
+    let cancelled = canceller.executed.clone();
+
+    let mut engine = Engine::<Event, Action>::new();
+    engine.add_collector_for::<event::Block>(FiniteCollector(vec![1, 2]));
+    engine.add_strategy_for::<event::Block, action::SendTx>(BlockStrategy);
+    engine.add_executor_for::<action::SendTx>(sender);
+    engine.add_executor_for::<action::Cancel>(canceller);
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    assert_eq!(*sent.lock().unwrap(), vec![10, 20]);
+    assert!(cancelled.lock().unwrap().is_empty());
+}
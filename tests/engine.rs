@@ -0,0 +1,1253 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+use futures::{Stream, StreamExt};
+use harpoon::collector::SimulatedIntervalCollector;
+use harpoon::engine::{
+    ComponentKind, ComponentState, CorrelationId, EngineEvent, Envelope, EventMeta, ExecutionReport, ExecutorConfig,
+    FileSnapshotStore, ISnapshotStore, OverflowPolicy, PanicPolicy, RestartPolicy, ShardKey, SnapshotConfig,
+};
+use harpoon::{
+    ActionId, CollectorMap, Engine, ExecutionOutcome, ExecutorMap, IActionSubmitter, ICollector, IExecutor, IStrategy,
+    SimulatedClock, async_trait, interested_in,
+};
+
+type EventStream<'a, E> = Pin<Box<dyn Stream<Item = E> + Send + 'a>>;
+
+/// Emits `0..count` and then stays pending, like a live subscription with no new data.
+struct CountingCollector {
+    count: u64,
+}
+
+#[async_trait]
+impl ICollector<u64> for CountingCollector {
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        let count = self.count;
+        let stream = async_stream::stream! {
+            for i in 0..count {
+                yield i;
+            }
+            futures::future::pending::<()>().await;
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// The first subscription yields `1` and ends, the second one fails and the third one yields `2` and stays open.
+#[derive(Default)]
+struct FlakyCollector {
+    calls: AtomicU64,
+}
+
+#[async_trait]
+impl ICollector<u64> for FlakyCollector {
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        match self.calls.fetch_add(1, Ordering::Relaxed) {
+            0 => Ok(Box::pin(futures::stream::iter([1]))),
+            1 => eyre::bail!("connection refused"),
+            _ => Ok(Box::pin(futures::stream::iter([2]).chain(futures::stream::pending()))),
+        }
+    }
+}
+
+/// Emits whatever is sent through `sender`, for tests that drive events one at a time.
+struct ChannelCollector {
+    receiver: Mutex<Option<tokio::sync::mpsc::UnboundedReceiver<u64>>>,
+}
+
+impl ChannelCollector {
+    fn new() -> (Self, tokio::sync::mpsc::UnboundedSender<u64>) {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let collector = Self {
+            receiver: Mutex::new(Some(receiver)),
+        };
+
+        (collector, sender)
+    }
+}
+
+#[async_trait]
+impl ICollector<u64> for ChannelCollector {
+    fn name(&self) -> &str {
+        "channel"
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        let mut receiver = self.receiver.lock().unwrap().take().expect("subscribed once");
+        let stream = async_stream::stream! {
+            while let Some(event) = receiver.recv().await {
+                yield event;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Submits twice the value of every event.
+struct DoublingStrategy;
+
+#[async_trait]
+impl IStrategy<u64, u64> for DoublingStrategy {
+    fn name(&self) -> &str {
+        "doubling"
+    }
+
+    async fn process_event(&mut self, event: u64, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        submitter.submit(event * 2);
+    }
+}
+
+#[derive(Default)]
+struct ForwardStrategy {
+    engine_events: Arc<Mutex<Vec<EngineEvent>>>,
+    submitted: Arc<Mutex<Vec<ActionId>>>,
+    reports: Arc<Mutex<Vec<ExecutionReport>>>,
+}
+
+#[async_trait]
+impl IStrategy<u64, u64> for ForwardStrategy {
+    fn name(&self) -> &str {
+        "forward"
+    }
+
+    async fn process_event(&mut self, event: u64, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        if let Some(id) = submitter.submit_tracked(event) {
+            self.submitted.lock().unwrap().push(id);
+        }
+    }
+
+    async fn on_engine_event(&mut self, event: EngineEvent, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.engine_events.lock().unwrap().push(event);
+    }
+
+    async fn on_execution_report(&mut self, report: ExecutionReport, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.reports.lock().unwrap().push(report);
+    }
+}
+
+#[derive(Clone, Default)]
+struct RecordingExecutor {
+    delay: Duration,
+    executed: Arc<Mutex<Vec<u64>>>,
+}
+
+#[async_trait]
+impl IExecutor<u64> for RecordingExecutor {
+    async fn execute(&self, action: u64) -> eyre::Result<()> {
+        tokio::time::sleep(self.delay).await;
+        self.executed.lock().unwrap().push(action);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_shutdown_drains_pending_actions() {
+    let executor = RecordingExecutor {
+        delay: Duration::from_millis(10),
+        ..Default::default()
+    };
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new().with_shutdown_timeout(Duration::from_secs(5));
+    engine.add_collector(Box::new(CountingCollector { count: 10 }));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), (0..10).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_shutdown_timeout_aborts_executors() {
+    let executor = RecordingExecutor {
+        delay: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new().with_shutdown_timeout(Duration::from_millis(50));
+    engine.add_collector(Box::new(CountingCollector { count: 1 }));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert!(executed.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_collector_restarts_after_failure() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let strategy = ForwardStrategy::default();
+    let engine_events = strategy.engine_events.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector_with_restart_policy(
+        Box::new(FlakyCollector::default()),
+        RestartPolicy::default()
+            .with_initial_backoff(Duration::from_millis(10))
+            .with_jitter(0.0),
+    );
+    engine.add_strategy(Box::new(strategy));
+    engine.add_executor(Box::new(executor));
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), vec![1, 2]);
+
+    let engine_events = engine_events.lock().unwrap();
+    assert_eq!(engine_events.len(), 1);
+    assert!(matches!(
+        &engine_events[0],
+        EngineEvent::CollectorRestarted { attempt: 2, .. }
+    ));
+}
+
+#[tokio::test]
+async fn test_slow_executor_does_not_hold_back_others() {
+    let slow = RecordingExecutor {
+        delay: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let fast = RecordingExecutor::default();
+    let executed = fast.executed.clone();
+
+    let mut engine = Engine::new().with_shutdown_timeout(Duration::from_millis(100));
+    engine.add_collector(Box::new(CountingCollector { count: 100 }));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor_with_config(
+        Box::new(slow),
+        ExecutorConfig::default()
+            .with_capacity(1)
+            .with_overflow(OverflowPolicy::DropNewest),
+    );
+    engine.add_executor_with_config(Box::new(fast), ExecutorConfig::default().with_concurrency(4));
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    let mut executed = executed.lock().unwrap().clone();
+    executed.sort();
+    assert_eq!(executed, (0..100).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn test_rejected_actions_are_reported_as_failed() {
+    let strategy = ForwardStrategy::default();
+    let submitted = strategy.submitted.clone();
+    let reports = strategy.reports.clone();
+
+    let mut engine = Engine::new().with_shutdown_timeout(Duration::from_millis(100));
+    engine.add_collector(Box::new(CountingCollector { count: 3 }));
+    engine.add_strategy(Box::new(strategy));
+    engine.add_executor_with_config(
+        Box::new(RecordingExecutor {
+            delay: Duration::from_secs(60),
+            ..Default::default()
+        }),
+        ExecutorConfig::default()
+            .with_capacity(1)
+            .with_overflow(OverflowPolicy::Fail),
+    );
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    // The first action is executing or queued, the last one can't fit behind it.
+    let submitted = submitted.lock().unwrap().clone();
+    let reports = reports.lock().unwrap();
+    assert!(reports.iter().all(|r| r.action_id != submitted[0]));
+    assert!(reports.iter().any(|r| r.action_id == submitted[2]));
+    for report in reports.iter() {
+        assert_eq!(
+            report.outcome,
+            ExecutionOutcome::Failed {
+                error: "action queue full".to_string(),
+                tx_hash: None
+            }
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_full_queue_holds_back_strategy_on_current_thread_runtime() {
+    let executor = RecordingExecutor {
+        delay: Duration::from_millis(1),
+        ..Default::default()
+    };
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(FiniteCollector((0..20).collect())));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor_with_config(
+        Box::new(executor),
+        ExecutorConfig::default()
+            .with_capacity(1)
+            .with_overflow(OverflowPolicy::Block),
+    );
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), (0..20).collect::<Vec<_>>());
+}
+
+/// Fails on odd actions.
+struct EvenExecutor;
+
+#[async_trait]
+impl IExecutor<u64> for EvenExecutor {
+    fn name(&self) -> &str {
+        "Even"
+    }
+
+    async fn execute(&self, action: u64) -> eyre::Result<()> {
+        eyre::ensure!(action.is_multiple_of(2), "odd action {action}");
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_execution_reports_reach_strategy() {
+    let strategy = ForwardStrategy::default();
+    let submitted = strategy.submitted.clone();
+    let reports = strategy.reports.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(CountingCollector { count: 4 }));
+    engine.add_strategy(Box::new(strategy));
+    engine.add_executor(Box::new(EvenExecutor));
+    // Only handles action 3, so it is skipped for the others and doesn't report them.
+    engine.add_executor(Box::new(ExecutorMap::new(
+        Box::new(RecordingExecutor::default()),
+        |action: u64| (action == 3).then_some(action),
+    )));
+
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    let submitted = submitted.lock().unwrap().clone();
+    assert_eq!(submitted.len(), 4);
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 5);
+
+    let even_report = |i: usize| {
+        reports
+            .iter()
+            .find(|r| r.action_id == submitted[i] && r.executor == "Even")
+            .unwrap()
+    };
+    for i in 0..submitted.len() {
+        assert_eq!(even_report(i).outcome.is_success(), i % 2 == 0);
+        assert!(even_report(i).correlation_id.is_some());
+    }
+
+    let mapped: Vec<_> = reports.iter().filter(|r| r.executor == "Unnamed").collect();
+    assert_eq!(mapped.len(), 1);
+    assert_eq!(mapped[0].action_id, submitted[3]);
+    assert_eq!(mapped[0].outcome, ExecutionOutcome::Succeeded { tx_hash: None });
+    assert_eq!(mapped[0].correlation_id, even_report(3).correlation_id);
+    assert_ne!(even_report(2).correlation_id, even_report(3).correlation_id);
+}
+
+struct FiniteCollector(Vec<u64>);
+
+#[async_trait]
+impl ICollector<u64> for FiniteCollector {
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        Ok(Box::pin(futures::stream::iter(self.0.clone())))
+    }
+}
+
+#[tokio::test]
+async fn test_engine_stops_when_finite_collectors_finish() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(FiniteCollector(vec![1, 2])));
+    engine.add_collector(Box::new(FiniteCollector(vec![3])));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    let mut executed = executed.lock().unwrap().clone();
+    executed.sort();
+    assert_eq!(executed, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_finite_collectors_do_not_lag_strategies() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new().with_event_channel_capacity(4);
+    engine.add_collector(Box::new(FiniteCollector((0..100).collect())));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), (0..100).collect::<Vec<_>>());
+}
+
+/// A finite collector whose stream never starts.
+struct BrokenFiniteCollector;
+
+#[async_trait]
+impl ICollector<u64> for BrokenFiniteCollector {
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        eyre::bail!("archive node unreachable")
+    }
+}
+
+#[tokio::test]
+async fn test_engine_stops_when_finite_collector_gives_up() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(FiniteCollector(vec![1])));
+    engine.add_collector_with_restart_policy(
+        Box::new(BrokenFiniteCollector),
+        RestartPolicy::default()
+            .with_initial_backoff(Duration::from_millis(1))
+            .with_max_retries(2),
+    );
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), vec![1]);
+}
+
+/// Emits `1..=count` as seconds since the epoch, moving `clock` along like a historical block collector.
+struct SimulatedBlocks {
+    count: u64,
+    clock: SimulatedClock,
+}
+
+#[async_trait]
+impl ICollector<u64> for SimulatedBlocks {
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        let stream = async_stream::stream! {
+            for timestamp in 1..=self.count {
+                self.clock.advance_to_unix_timestamp(timestamp);
+                self.clock.settled().await;
+                yield timestamp;
+            }
+        };
+
+        Ok(Box::pin(stream))
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_with_simulated_clock() {
+    const TICK: u64 = u64::MAX;
+
+    let clock = SimulatedClock::default();
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new().with_clock(clock.clone().into());
+    engine.add_collector(Box::new(SimulatedBlocks {
+        count: 10,
+        clock: clock.clone(),
+    }));
+    engine.add_collector(Box::new(CollectorMap::new(
+        Box::new(SimulatedIntervalCollector::new(Duration::from_secs(2), clock.clone())),
+        |_| TICK,
+    )));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    // Ticks every 2 seconds, each right before the block sharing its timestamp.
+    let expected = vec![1, TICK, 2, 3, TICK, 4, 5, TICK, 6, 7, TICK, 8, 9, TICK, 10];
+    assert_eq!(*executed.lock().unwrap(), expected);
+    assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn test_engine_handle_pauses_strategy() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+    let (collector, events) = ChannelCollector::new();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(collector));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    let handle = engine.handle();
+    let shutdown = engine.shutdown_handle();
+    let control = async {
+        let settle = || tokio::time::sleep(Duration::from_millis(50));
+
+        events.send(1).unwrap();
+        settle().await;
+
+        handle.pause("forward").unwrap();
+        assert_eq!(handle.status_of("forward")[0].state, ComponentState::Paused);
+        events.send(2).unwrap();
+        settle().await;
+
+        handle.resume("forward").unwrap();
+        events.send(3).unwrap();
+        settle().await;
+
+        let status = handle.status_of("forward");
+        assert_eq!(status[0].state, ComponentState::Running);
+        assert_eq!(status[0].processed, 2);
+        assert_eq!(handle.status_of("channel")[0].processed, 3);
+        assert!(handle.pause("missing").is_err());
+
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), control)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), vec![1, 3]);
+    assert!(
+        handle
+            .status()
+            .iter()
+            .all(|status| status.state == ComponentState::Stopped)
+    );
+}
+
+#[tokio::test]
+async fn test_engine_handle_adds_and_removes_components() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+    let (collector, events) = ChannelCollector::new();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(collector));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    let handle = engine.handle();
+    assert!(handle.add_strategy(Box::new(DoublingStrategy)).await.is_err());
+
+    let shutdown = engine.shutdown_handle();
+    let control = async {
+        let settle = || tokio::time::sleep(Duration::from_millis(50));
+
+        events.send(1).unwrap();
+        settle().await;
+
+        handle.add_strategy(Box::new(DoublingStrategy)).await.unwrap();
+        events.send(2).unwrap();
+        settle().await;
+
+        handle.remove("doubling").unwrap();
+        assert!(handle.status_of("doubling").is_empty());
+        events.send(3).unwrap();
+        settle().await;
+
+        // Strategies would lose their event channel.
+        assert!(handle.remove("channel").is_err());
+
+        handle.add_collector(Box::new(CountingCollector { count: 2 })).unwrap();
+        settle().await;
+        handle.remove("channel").unwrap();
+
+        let collectors = handle
+            .status()
+            .into_iter()
+            .filter(|status| status.kind == ComponentKind::Collector)
+            .collect::<Vec<_>>();
+        assert_eq!(collectors.len(), 1);
+        assert_eq!(collectors[0].processed, 2);
+
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), control)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    let mut executed = executed.lock().unwrap().clone();
+    executed.sort();
+    assert_eq!(executed, vec![0, 1, 1, 2, 3, 4]);
+}
+
+#[tokio::test]
+async fn test_engine_handle_replaces_every_strategy() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+    let (collector, events) = ChannelCollector::new();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(collector));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(executor));
+
+    let handle = engine.handle();
+    let shutdown = engine.shutdown_handle();
+    let control = async {
+        let settle = || tokio::time::sleep(Duration::from_millis(50));
+
+        // Executors keep running without any strategy.
+        handle.remove("forward").unwrap();
+        settle().await;
+
+        handle.add_strategy(Box::new(DoublingStrategy)).await.unwrap();
+        events.send(1).unwrap();
+        settle().await;
+
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), control)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    // The engine waited for the added strategy to stop.
+    assert_eq!(handle.status_of("doubling")[0].state, ComponentState::Stopped);
+    assert_eq!(*executed.lock().unwrap(), vec![2]);
+}
+
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let address = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let mut engine = Engine::new().with_metrics_endpoint(address);
+    engine.add_collector(Box::new(CountingCollector { count: 4 }));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_executor(Box::new(EvenExecutor));
+
+    let shutdown = engine.shutdown_handle();
+    let scrape = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+
+        shutdown.shutdown();
+        response
+    };
+
+    let (result, response) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), scrape)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+
+    for line in [
+        r#"harpoon_collector_events_total{collector="Unnamed"} 4"#,
+        r#"harpoon_strategy_events_processed_total{strategy="forward"} 4"#,
+        r#"harpoon_strategy_events_lagged_total{strategy="forward"} 0"#,
+        r#"harpoon_strategy_process_event_seconds_count{strategy="forward"} 4"#,
+        r#"harpoon_executor_actions_submitted_total{executor="Even"} 4"#,
+        r#"harpoon_executor_actions_executed_total{executor="Even"} 2"#,
+        r#"harpoon_executor_actions_failed_total{executor="Even"} 2"#,
+        r#"harpoon_executor_execute_seconds_count{executor="Even"} 4"#,
+    ] {
+        assert!(response.contains(line), "missing `{line}` in:\n{response}");
+    }
+}
+
+/// Panics on event `2`, and counts how often its state was synced.
+#[derive(Default)]
+struct PanickingStrategy {
+    syncs: Arc<AtomicU64>,
+    processed: Arc<Mutex<Vec<u64>>>,
+    engine_events: Arc<Mutex<Vec<EngineEvent>>>,
+}
+
+#[async_trait]
+impl IStrategy<u64, u64> for PanickingStrategy {
+    fn name(&self) -> &str {
+        "panicking"
+    }
+
+    async fn sync_state(&mut self, _submitter: Arc<dyn IActionSubmitter<u64>>) -> eyre::Result<()> {
+        self.syncs.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    async fn process_event(&mut self, event: u64, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        assert_ne!(event, 2, "cannot handle event");
+        self.processed.lock().unwrap().push(event);
+    }
+
+    async fn on_engine_event(&mut self, event: EngineEvent, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.engine_events.lock().unwrap().push(event);
+    }
+}
+
+/// Panics on action `2`.
+struct PanickingExecutor;
+
+#[async_trait]
+impl IExecutor<u64> for PanickingExecutor {
+    fn name(&self) -> &str {
+        "panicking"
+    }
+
+    async fn execute(&self, action: u64) -> eyre::Result<()> {
+        assert_ne!(action, 2, "cannot execute action");
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_strategy_restarts_after_panic() {
+    let strategy = PanickingStrategy::default();
+    let syncs = strategy.syncs.clone();
+    let processed = strategy.processed.clone();
+    let engine_events = strategy.engine_events.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(CountingCollector { count: 4 }));
+    engine.add_strategy(Box::new(strategy));
+    engine.add_executor(Box::new(RecordingExecutor::default()));
+
+    let handle = engine.handle();
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(handle.status_of("panicking")[0].state, ComponentState::Running);
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    assert_eq!(*processed.lock().unwrap(), vec![0, 1, 3]);
+    assert_eq!(syncs.load(Ordering::Relaxed), 2);
+
+    let engine_events = engine_events.lock().unwrap();
+    assert!(matches!(
+        &engine_events[..],
+        [EngineEvent::StrategyPanicked { input, panic, quarantined: false, .. }]
+            if input == "2" && panic.contains("cannot handle event")
+    ));
+}
+
+#[tokio::test]
+async fn test_executor_quarantined_after_panic() {
+    let strategy = ForwardStrategy::default();
+    let reports = strategy.reports.clone();
+    let engine_events = strategy.engine_events.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(CountingCollector { count: 6 }));
+    engine.add_strategy(Box::new(strategy));
+    // Action 4 isn't for this executor, so it is skipped rather than failed once the executor is quarantined.
+    engine.add_executor_with_config(
+        Box::new(
+            ExecutorMap::new(Box::new(PanickingExecutor), |action: u64| {
+                (action != 4).then_some(action)
+            })
+            .with_ref(|action: &u64| (*action != 4).then_some(action)),
+        ),
+        ExecutorConfig::default().with_panic_policy(PanicPolicy::Quarantine),
+    );
+
+    let handle = engine.handle();
+    let shutdown = engine.shutdown_handle();
+    let stop = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(handle.status_of("panicking")[0].state, ComponentState::Quarantined);
+        shutdown.shutdown();
+    };
+
+    let (result, _) = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::join!(engine.run_and_join(), stop)
+    })
+    .await
+    .expect("engine did not stop");
+    result.unwrap();
+
+    let reports = reports.lock().unwrap();
+    let outcomes = reports
+        .iter()
+        .map(|report| match &report.outcome {
+            ExecutionOutcome::Failed { error, .. } => error.clone(),
+            outcome => format!("{outcome:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(outcomes.len(), 5);
+    assert!(outcomes[0].starts_with("Succeeded"));
+    assert!(outcomes[1].starts_with("Succeeded"));
+    assert!(outcomes[2].contains("cannot execute action"));
+    assert_eq!(outcomes[3], "executor quarantined");
+    assert_eq!(outcomes[4], "executor quarantined");
+    assert_eq!(reports[4].action_id, ActionId(5));
+
+    let engine_events = engine_events.lock().unwrap();
+    assert!(matches!(
+        &engine_events[..],
+        [EngineEvent::ExecutorPanicked { action, quarantined: true, .. }] if action == "2"
+    ));
+}
+
+static TRACKED_CLONES: AtomicU64 = AtomicU64::new(0);
+
+/// Counts how often it is cloned.
+#[derive(Debug)]
+enum TrackedEvent {
+    Block(u64),
+    Transaction(u64),
+}
+
+impl Clone for TrackedEvent {
+    fn clone(&self) -> Self {
+        TRACKED_CLONES.fetch_add(1, Ordering::Relaxed);
+
+        match self {
+            Self::Block(number) => Self::Block(*number),
+            Self::Transaction(nonce) => Self::Transaction(*nonce),
+        }
+    }
+}
+
+/// Submits the number of every block.
+struct BlockStrategy;
+
+#[async_trait]
+impl IStrategy<TrackedEvent, u64> for BlockStrategy {
+    fn is_interested(&self, event: &TrackedEvent) -> bool {
+        interested_in!(event, TrackedEvent::Block)
+    }
+
+    async fn process_event(&mut self, event: TrackedEvent, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        match event {
+            TrackedEvent::Block(number) => submitter.submit(number),
+            TrackedEvent::Transaction(_) => panic!("not interested in transactions"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_strategies_only_receive_events_they_are_interested_in() {
+    let executor = RecordingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(CollectorMap::new(
+        Box::new(FiniteCollector((0..6).collect())),
+        |i| match i % 2 {
+            0 => TrackedEvent::Block(i),
+            _ => TrackedEvent::Transaction(i),
+        },
+    )));
+    engine.add_strategy(Box::new(BlockStrategy));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    assert_eq!(*executed.lock().unwrap(), vec![0, 2, 4]);
+    assert_eq!(TRACKED_CLONES.load(Ordering::Relaxed), 3);
+}
+
+/// Records events in the order they are processed, sharded by parity; event `0` takes a while.
+struct ParityStrategy {
+    processed: Arc<Mutex<Vec<u64>>>,
+}
+
+#[async_trait]
+impl IStrategy<u64, u64> for ParityStrategy {
+    fn name(&self) -> &str {
+        "parity"
+    }
+
+    fn shard_key(&self, event: &u64) -> Option<ShardKey> {
+        Some(ShardKey::from_u64(event % 2))
+    }
+
+    async fn process_event(&mut self, event: u64, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        if event == 0 {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        self.processed.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test]
+async fn test_sharded_strategy_keeps_order_per_key() {
+    let processed = Arc::new(Mutex::new(vec![]));
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(FiniteCollector((0..6).collect())));
+    engine.add_sharded_strategy(2, |_| {
+        Box::new(ParityStrategy {
+            processed: processed.clone(),
+        })
+    });
+    engine.add_executor(Box::new(RecordingExecutor::default()));
+
+    let handle = engine.handle();
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    // The slow event only held back its own shard.
+    assert_eq!(*processed.lock().unwrap(), vec![1, 3, 5, 0, 2, 4]);
+    assert_eq!(handle.status_of("parity").len(), 2);
+}
+
+/// Records the correlation id of every action it executes, along with the action.
+#[derive(Default)]
+struct CorrelatingExecutor {
+    executed: Arc<Mutex<HashMap<u64, CorrelationId>>>,
+}
+
+#[async_trait]
+impl IExecutor<u64> for CorrelatingExecutor {
+    async fn execute(&self, action: u64) -> eyre::Result<()> {
+        let correlation_id = CorrelationId::current().expect("action submitted while processing an event");
+        self.executed.lock().unwrap().insert(action, correlation_id);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_actions_carry_correlation_id_of_their_event() {
+    let executor = CorrelatingExecutor::default();
+    let executed = executor.executed.clone();
+
+    let mut engine = Engine::new();
+    engine.add_collector(Box::new(FiniteCollector(vec![1, 3, 5])));
+    engine.add_strategy(Box::new(ForwardStrategy::default()));
+    engine.add_strategy(Box::new(DoublingStrategy));
+    engine.add_executor(Box::new(executor));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    let executed = executed.lock().unwrap().clone();
+    assert_eq!(executed.len(), 6);
+
+    // Both strategies' actions are traced back to the event they were submitted for.
+    let correlation_id = |action: u64| executed[&action];
+    assert_eq!(correlation_id(1), correlation_id(2));
+    assert_eq!(correlation_id(3), correlation_id(6));
+    assert_eq!(correlation_id(5), correlation_id(10));
+    assert_ne!(correlation_id(1), correlation_id(3));
+    assert_ne!(correlation_id(3), correlation_id(5));
+
+    assert_eq!(CorrelationId::current(), None);
+}
+
+/// A finite collector following chain 56.
+struct ChainCollector(Vec<u64>);
+
+#[async_trait]
+impl ICollector<u64> for ChainCollector {
+    fn name(&self) -> &str {
+        "chain"
+    }
+
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn chain_id(&self) -> Option<u64> {
+        Some(56)
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        Ok(Box::pin(futures::stream::iter(self.0.clone())))
+    }
+}
+
+/// Records the metadata of every event it processes.
+#[derive(Default)]
+struct EnvelopeStrategy {
+    received: Arc<Mutex<Vec<(String, EventMeta)>>>,
+}
+
+#[async_trait]
+impl IStrategy<String, u64> for EnvelopeStrategy {
+    async fn process_event(&mut self, _event: String, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        unreachable!("events arrive through process_envelope");
+    }
+
+    async fn process_envelope(&mut self, envelope: Envelope<String>, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.received.lock().unwrap().push((envelope.event, envelope.meta));
+    }
+}
+
+#[tokio::test]
+async fn test_events_arrive_with_metadata() {
+    let strategy = EnvelopeStrategy::default();
+    let received = strategy.received.clone();
+    let clock = SimulatedClock::new(UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+
+    let mut engine = Engine::new().with_clock(clock.clone().into());
+    engine.add_collector(Box::new(CollectorMap::new(
+        Box::new(ChainCollector(vec![1, 2])),
+        |n: u64| n.to_string(),
+    )));
+    engine.add_strategy(Box::new(strategy));
+    engine.add_executor(Box::new(RecordingExecutor::default()));
+
+    tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+        .await
+        .expect("engine did not stop")
+        .unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+
+    for (i, (event, meta)) in received.iter().enumerate() {
+        assert_eq!(*event, (i + 1).to_string());
+        assert_eq!(&*meta.collector, "chain");
+        assert_eq!(meta.chain_id, Some(56));
+        assert_eq!(meta.sequence, i as u64);
+        assert_eq!(meta.correlation_id(), CorrelationId(i as u64));
+        assert_eq!(meta.received_time, clock.now());
+    }
+    assert!(received[0].1.received_at <= received[1].1.received_at);
+}
+
+/// Keeps a running total of its events, submitting each event along with the total so far.
+struct InventoryStrategy {
+    total: u64,
+    synced_total: Arc<Mutex<Option<u64>>>,
+}
+
+#[async_trait]
+impl IStrategy<u64, u64> for InventoryStrategy {
+    fn name(&self) -> &str {
+        "inventory"
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        Some(self.total.to_le_bytes().to_vec())
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> eyre::Result<()> {
+        self.total = u64::from_le_bytes(snapshot.try_into()?);
+        Ok(())
+    }
+
+    async fn sync_state(&mut self, _submitter: Arc<dyn IActionSubmitter<u64>>) -> eyre::Result<()> {
+        *self.synced_total.lock().unwrap() = Some(self.total);
+        Ok(())
+    }
+
+    async fn process_event(&mut self, event: u64, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.total += event;
+        submitter.submit(self.total);
+    }
+}
+
+/// A finite collector that flags when its stream starts.
+struct StartedCollector(Vec<u64>, Arc<AtomicBool>);
+
+#[async_trait]
+impl ICollector<u64> for StartedCollector {
+    fn is_finite(&self) -> bool {
+        true
+    }
+
+    async fn get_event_stream(&self) -> eyre::Result<EventStream<'_, u64>> {
+        self.1.store(true, Ordering::SeqCst);
+        Ok(Box::pin(futures::stream::iter(self.0.clone())))
+    }
+}
+
+#[tokio::test]
+async fn test_strategy_restores_snapshot_and_replays_missed_events() {
+    let dir = std::env::temp_dir().join(format!("harpoon-snapshots-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let run = |events: Vec<u64>, replayed: Vec<u64>| {
+        let dir = dir.clone();
+        async move {
+            let executor = RecordingExecutor::default();
+            let executed = executor.executed.clone();
+            let synced_total = Arc::new(Mutex::new(None));
+            let replay_since = Arc::new(Mutex::new(None));
+            let live_started = Arc::new(AtomicBool::new(false));
+
+            // Replays must be done before live events start coming in.
+            let since = replay_since.clone();
+            let started = live_started.clone();
+            let snapshots = SnapshotConfig::new(FileSnapshotStore::new(&dir)).with_replay(move |taken_at| {
+                assert!(!started.load(Ordering::SeqCst), "collectors started before the replay");
+                *since.lock().unwrap() = Some(taken_at);
+                Box::new(FiniteCollector(replayed.clone()))
+            });
+
+            let mut engine = Engine::new().with_snapshots(snapshots);
+            engine.add_collector(Box::new(StartedCollector(events, live_started)));
+            engine.add_strategy(Box::new(InventoryStrategy {
+                total: 0,
+                synced_total: synced_total.clone(),
+            }));
+            engine.add_executor(Box::new(executor));
+
+            tokio::time::timeout(Duration::from_secs(5), engine.run_and_join())
+                .await
+                .expect("engine did not stop")
+                .unwrap();
+
+            let executed = executed.lock().unwrap().clone();
+            let synced_total = *synced_total.lock().unwrap();
+            let replay_since = *replay_since.lock().unwrap();
+            (executed, synced_total, replay_since)
+        }
+    };
+
+    // Nothing to restore on the first run; the state is saved when the strategy stops.
+    let (executed, synced_total, replay_since) = run(vec![1, 2, 3], vec![]).await;
+    assert_eq!(executed, vec![1, 3, 6]);
+    assert_eq!(synced_total, Some(0));
+    assert_eq!(replay_since, None);
+
+    // The second run restores the total, replays what was missed without executing it again, then syncs.
+    let (executed, synced_total, replay_since) = run(vec![10], vec![4, 5]).await;
+    assert_eq!(synced_total, Some(15));
+    assert_eq!(executed, vec![25]);
+    assert!(replay_since.is_some());
+
+    let snapshot = FileSnapshotStore::new(&dir).load("inventory").await.unwrap().unwrap();
+    assert_eq!(snapshot.state, 25u64.to_le_bytes());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,180 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use harpoon::engine::RestartPolicy;
+use harpoon::executor::{CircuitBreaker, CircuitState, RateLimit, Retry, Timeout};
+use harpoon::{ExecutionOutcome, IExecutor, async_trait};
+use tokio::time::Instant;
+
+/// Fails with the scripted errors in order, then succeeds; records when it was called.
+#[derive(Default)]
+struct ScriptedExecutor {
+    errors: Mutex<Vec<&'static str>>,
+    calls: std::sync::Arc<Mutex<Vec<Instant>>>,
+    delay: Duration,
+}
+
+impl ScriptedExecutor {
+    fn failing(errors: &[&'static str]) -> Self {
+        Self {
+            errors: Mutex::new(errors.iter().rev().copied().collect()),
+            ..Self::default()
+        }
+    }
+}
+
+#[async_trait]
+impl IExecutor<u64> for ScriptedExecutor {
+    async fn execute(&self, _action: u64) -> eyre::Result<()> {
+        self.calls.lock().unwrap().push(Instant::now());
+        tokio::time::sleep(self.delay).await;
+
+        match self.errors.lock().unwrap().pop() {
+            Some(error) => eyre::bail!(error),
+            None => Ok(()),
+        }
+    }
+}
+
+fn failed(error: &str) -> ExecutionOutcome {
+    ExecutionOutcome::Failed {
+        error: error.to_string(),
+        tx_hash: None,
+    }
+}
+
+fn policy() -> RestartPolicy {
+    RestartPolicy::default()
+        .with_initial_backoff(Duration::from_millis(100))
+        .with_jitter(0.0)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_backs_off_until_success() {
+    let executor = ScriptedExecutor::failing(&["429", "429"]);
+    let calls = executor.calls.clone();
+    let retry = Retry::new(Box::new(executor), policy().with_max_retries(3));
+
+    let start = Instant::now();
+    assert!(retry.execute_with_outcome(1).await.is_success());
+
+    let calls = calls.lock().unwrap();
+    let offsets = calls.iter().map(|t| t.duration_since(start)).collect::<Vec<_>>();
+    assert_eq!(offsets, [0, 100, 300].map(Duration::from_millis));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_gives_up() {
+    let retry = Retry::new(
+        Box::new(ScriptedExecutor::failing(&["429", "429", "429"])),
+        policy().with_max_retries(2),
+    );
+    assert_eq!(retry.execute_with_outcome(1).await, failed("429"));
+
+    // Errors the classifier rejects are returned right away.
+    let executor = ScriptedExecutor::failing(&["nonce too low", "429"]);
+    let calls = executor.calls.clone();
+    let retry = Retry::new(Box::new(executor), policy())
+        .with_classifier(|outcome| matches!(outcome, ExecutionOutcome::Failed { error, .. } if error == "429"));
+    assert_eq!(retry.execute_with_outcome(1).await, failed("nonce too low"));
+    assert_eq!(calls.lock().unwrap().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_defaults_to_one_retry_of_transient_errors() {
+    // Permanent errors aren't retried.
+    let executor = ScriptedExecutor::failing(&["missing signer"]);
+    let calls = executor.calls.clone();
+    let retry = Retry::new(Box::new(executor), policy());
+    assert_eq!(retry.execute_with_outcome(1).await, failed("missing signer"));
+    assert_eq!(calls.lock().unwrap().len(), 1);
+
+    // Without `max_retries`, transient errors are retried once.
+    let executor = ScriptedExecutor::failing(&["timed out after 1s", "connection reset by peer"]);
+    let calls = executor.calls.clone();
+    let retry = Retry::new(Box::new(executor), policy());
+    assert_eq!(retry.execute_with_outcome(1).await, failed("connection reset by peer"));
+    assert_eq!(calls.lock().unwrap().len(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_rate_limit_spaces_out_calls() {
+    let executor = ScriptedExecutor::default();
+    let calls = executor.calls.clone();
+    let limited = RateLimit::new(Box::new(executor), 10.0, 2);
+
+    let start = Instant::now();
+    for action in 0..5 {
+        limited.execute(action).await.unwrap();
+    }
+
+    let calls = calls.lock().unwrap();
+    let offsets = calls.iter().map(|t| t.duration_since(start)).collect::<Vec<_>>();
+    assert_eq!(offsets, [0, 0, 100, 200, 300].map(Duration::from_millis));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_timeout_fails_slow_calls() {
+    let executor = ScriptedExecutor {
+        delay: Duration::from_secs(2),
+        ..ScriptedExecutor::default()
+    };
+    let timeout = Timeout::new(Box::new(executor), Duration::from_secs(1));
+
+    assert_eq!(timeout.execute_with_outcome(1).await, failed("timed out after 1s"));
+    assert!(timeout.execute(1).await.is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_opens_and_recovers() {
+    let executor = ScriptedExecutor::failing(&["down", "down", "still down"]);
+    let calls = executor.calls.clone();
+    let breaker = CircuitBreaker::new(Box::new(executor), 2, Duration::from_secs(10));
+    let mut state = breaker.subscribe();
+
+    assert_eq!(breaker.execute_with_outcome(1).await, failed("down"));
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(breaker.execute_with_outcome(2).await, failed("down"));
+    assert_eq!(breaker.state(), CircuitState::Open);
+    assert!(state.has_changed().unwrap());
+    assert_eq!(*state.borrow_and_update(), CircuitState::Open);
+
+    // Open: fails without calling the executor.
+    assert_eq!(breaker.execute_with_outcome(3).await, failed("circuit open"));
+    assert_eq!(calls.lock().unwrap().len(), 2);
+
+    // The trial after the cooldown fails, which opens the circuit again.
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert_eq!(breaker.execute_with_outcome(4).await, failed("still down"));
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert!(breaker.execute_with_outcome(5).await.is_success());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(calls.lock().unwrap().len(), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_reopens_after_dropped_trial() {
+    let executor = ScriptedExecutor {
+        errors: Mutex::new(vec!["down"]),
+        delay: Duration::from_secs(1),
+        ..ScriptedExecutor::default()
+    };
+    let calls = executor.calls.clone();
+    let breaker = CircuitBreaker::new(Box::new(executor), 1, Duration::from_secs(10));
+
+    assert_eq!(breaker.execute_with_outcome(1).await, failed("down"));
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    // The trial is cancelled before the executor returns.
+    tokio::time::advance(Duration::from_secs(10)).await;
+    let trial = tokio::time::timeout(Duration::from_millis(100), breaker.execute_with_outcome(2)).await;
+    assert!(trial.is_err());
+    assert_eq!(breaker.state(), CircuitState::Open);
+
+    tokio::time::advance(Duration::from_secs(10)).await;
+    assert!(breaker.execute_with_outcome(3).await.is_success());
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert_eq!(calls.lock().unwrap().len(), 3);
+}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, RwLock};
+
+use harpoon::testing::StrategyHarness;
+use harpoon::{IActionSubmitter, IStrategy, StrategyGroup, async_trait, map_strategy};
+
+#[derive(Debug, Clone)]
+enum Event {
+    Transfer(u64),
+    Block(u64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    Alert(String),
+    Swap(u64),
+}
+
+/// Alerts on transfers above a threshold, unaware of the engine's enums.
+struct LargeTransferAlert {
+    threshold: u64,
+}
+
+#[async_trait]
+impl IStrategy<u64, String> for LargeTransferAlert {
+    fn is_interested(&self, amount: &u64) -> bool {
+        *amount >= self.threshold
+    }
+
+    async fn process_event(&mut self, amount: u64, submitter: Arc<dyn IActionSubmitter<String>>) {
+        submitter.submit(format!("transfer of {amount}"));
+    }
+}
+
+#[tokio::test]
+async fn test_map_strategy_projects_events_and_lifts_actions() {
+    let strategy = map_strategy!(LargeTransferAlert { threshold: 100 }, Event::Transfer, Action::Alert);
+    let mut harness = StrategyHarness::new(*strategy);
+
+    assert!(!harness.push(Event::Transfer(10)).await);
+    assert!(!harness.push(Event::Block(500)).await);
+    assert!(harness.push(Event::Transfer(500)).await);
+
+    assert_eq!(
+        harness.take_actions(),
+        vec![Action::Alert("transfer of 500".to_string())]
+    );
+}
+
+/// Keeps the latest block number in the group's state.
+struct BlockTracker(Arc<RwLock<u64>>);
+
+#[async_trait]
+impl IStrategy<Event, Action> for BlockTracker {
+    fn is_interested(&self, event: &Event) -> bool {
+        matches!(event, Event::Block(_))
+    }
+
+    async fn process_event(&mut self, event: Event, _submitter: Arc<dyn IActionSubmitter<Action>>) {
+        if let Event::Block(number) = event {
+            *self.0.write().unwrap() = number;
+        }
+    }
+}
+
+/// Swaps on each block, reading the number the tracker stored for it.
+struct BlockSwapper(Arc<RwLock<u64>>);
+
+#[async_trait]
+impl IStrategy<Event, Action> for BlockSwapper {
+    fn is_interested(&self, event: &Event) -> bool {
+        matches!(event, Event::Block(_))
+    }
+
+    async fn process_event(&mut self, _event: Event, submitter: Arc<dyn IActionSubmitter<Action>>) {
+        let number = *self.0.read().unwrap();
+        submitter.submit(Action::Swap(number));
+    }
+}
+
+#[tokio::test]
+async fn test_strategy_group_shares_state_between_members() {
+    let group = StrategyGroup::new("blocks", 0)
+        .with_strategy(|state| Box::new(BlockTracker(state)))
+        .with_strategy(|state| Box::new(BlockSwapper(state)))
+        .with_strategy(|_| map_strategy!(LargeTransferAlert { threshold: 100 }, Event::Transfer, Action::Alert));
+    let state = group.state();
+    let mut harness = StrategyHarness::new(group);
+
+    assert!(harness.push(Event::Block(7)).await);
+    assert!(!harness.push(Event::Transfer(1)).await);
+    assert!(harness.push(Event::Transfer(100)).await);
+    assert!(harness.push(Event::Block(8)).await);
+
+    assert_eq!(
+        harness.take_actions(),
+        vec![
+            Action::Swap(7),
+            Action::Alert("transfer of 100".to_string()),
+            Action::Swap(8)
+        ]
+    );
+    assert_eq!(*state.read().unwrap(), 8);
+}
+
+/// Counts the events of at least `min`, and keeps the count across restarts.
+struct Counter {
+    name: &'static str,
+    min: u64,
+    count: u64,
+}
+
+#[async_trait]
+impl IStrategy<u64, String> for Counter {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        Some(self.count.to_le_bytes().to_vec())
+    }
+
+    fn restore(&mut self, snapshot: &[u8]) -> eyre::Result<()> {
+        self.count = u64::from_le_bytes(snapshot.try_into()?);
+        Ok(())
+    }
+
+    fn is_interested(&self, event: &u64) -> bool {
+        *event >= self.min
+    }
+
+    async fn process_event(&mut self, _event: u64, submitter: Arc<dyn IActionSubmitter<String>>) {
+        self.count += 1;
+        submitter.submit(format!("{} {}", self.name, self.count));
+    }
+}
+
+fn counters() -> StrategyGroup<Event, Action, u64> {
+    let counter = |name, min| Counter { name, min, count: 0 };
+
+    // The two transfer counters share a name, so their snapshots are told apart by their position.
+    StrategyGroup::new("counters", 0)
+        .with_strategy(|state| Box::new(BlockTracker(state)))
+        .with_strategy(move |_| map_strategy!(counter("blocks", 0), Event::Block, Action::Alert))
+        .with_strategy(move |_| map_strategy!(counter("transfers", 0), Event::Transfer, Action::Alert))
+        .with_strategy(move |_| map_strategy!(counter("transfers", 10), Event::Transfer, Action::Alert))
+}
+
+#[tokio::test]
+async fn test_strategy_group_snapshots_every_member() {
+    let mut harness = StrategyHarness::new(counters());
+    harness.push(Event::Block(1)).await;
+    harness.push(Event::Transfer(5)).await;
+    let snapshot = harness.strategy().snapshot().unwrap();
+
+    let mut restored = counters();
+    restored.restore(&snapshot).unwrap();
+    let mut harness = StrategyHarness::new(restored);
+    harness.push(Event::Block(2)).await;
+    harness.push(Event::Transfer(20)).await;
+
+    assert_eq!(
+        harness.take_actions(),
+        vec![
+            Action::Alert("blocks 2".to_string()),
+            Action::Alert("transfers 2".to_string()),
+            Action::Alert("transfers 1".to_string()),
+        ]
+    );
+    assert!(counters().restore(&snapshot[..snapshot.len() - 1]).is_err());
+}
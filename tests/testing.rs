@@ -0,0 +1,133 @@
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use harpoon::engine::{CorrelationId, EngineEvent, ExecutionReport};
+use harpoon::testing::{RecordingSubmitter, StrategyHarness};
+use harpoon::{ActionId, ExecutionOutcome, IActionSubmitter, IStrategy, async_trait};
+use tokio::time::Instant;
+
+/// Submits even events, at most one per cooldown, and submits them again after a minute.
+#[derive(Default)]
+struct CooldownStrategy {
+    synced: bool,
+    last_submit: Option<Instant>,
+    pending: Option<ActionId>,
+    correlation_ids: Vec<CorrelationId>,
+    failures: usize,
+}
+
+#[async_trait]
+impl IStrategy<u64, u64> for CooldownStrategy {
+    async fn sync_state(&mut self, submitter: Arc<dyn IActionSubmitter<u64>>) -> eyre::Result<()> {
+        self.synced = true;
+        submitter.submit(0);
+        Ok(())
+    }
+
+    fn is_interested(&self, event: &u64) -> bool {
+        event.is_multiple_of(2)
+    }
+
+    async fn process_event(&mut self, event: u64, submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.correlation_ids.extend(CorrelationId::current());
+
+        if self
+            .last_submit
+            .is_some_and(|last| last.elapsed() < Duration::from_secs(10))
+        {
+            return;
+        }
+
+        self.last_submit = Some(Instant::now());
+        self.pending = submitter.submit_tracked(event);
+
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            submitter.submit(event);
+        });
+    }
+
+    async fn on_engine_event(&mut self, _event: EngineEvent, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        self.synced = false;
+    }
+
+    async fn on_execution_report(&mut self, report: ExecutionReport, _submitter: Arc<dyn IActionSubmitter<u64>>) {
+        if !report.outcome.is_success() {
+            self.failures += 1;
+        }
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_harness_records_submitted_actions() {
+    let mut harness = StrategyHarness::new(CooldownStrategy::default());
+
+    harness.sync_state().await.unwrap();
+    assert!(harness.strategy().synced);
+    assert_eq!(harness.take_actions(), vec![0]);
+
+    assert!(harness.push(2).await);
+    assert!(!harness.push(3).await);
+    assert!(harness.push(4).await);
+    assert_eq!(harness.take_actions(), vec![2]);
+    assert_eq!(
+        harness.strategy().correlation_ids,
+        vec![CorrelationId(0), CorrelationId(2)]
+    );
+
+    harness.advance(Duration::from_secs(10)).await;
+    harness.push(6).await;
+    assert_eq!(harness.take_actions(), vec![6]);
+
+    // The resubmission timers of 2 and 6 fire on tokio's paused clock.
+    harness.advance(Duration::from_secs(51)).await;
+    assert_eq!(harness.take_actions(), vec![2]);
+    harness.advance(Duration::from_secs(10)).await;
+    assert_eq!(harness.take_actions(), vec![6]);
+    assert_eq!(harness.clock().now(), UNIX_EPOCH + Duration::from_secs(71));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_harness_delivers_engine_events_and_reports() {
+    let mut harness = StrategyHarness::new(CooldownStrategy::default());
+    harness.sync_state().await.unwrap();
+
+    harness.push(2).await;
+    let action_id = harness.strategy().pending.unwrap();
+    assert_eq!(harness.submitter().tracked_actions().last(), Some(&(action_id, 2)));
+
+    harness
+        .push_report(ExecutionReport {
+            action_id,
+            correlation_id: None,
+            executor: "test".to_string(),
+            outcome: ExecutionOutcome::Failed {
+                error: "nonce too low".to_string(),
+                tx_hash: None,
+            },
+            started_at: UNIX_EPOCH,
+            elapsed: Duration::ZERO,
+        })
+        .await;
+    assert_eq!(harness.strategy().failures, 1);
+
+    harness
+        .push_engine_event(EngineEvent::CollectorStopped {
+            collector: "block".to_string(),
+            attempts: 3,
+        })
+        .await;
+    assert!(!harness.strategy().synced);
+}
+
+#[test]
+fn test_recording_submitter_tracks_actions() {
+    let submitter = RecordingSubmitter::new();
+    submitter.submit("a");
+    let id = submitter.submit_tracked("b");
+
+    assert_eq!(id, Some(ActionId(1)));
+    assert_eq!(submitter.actions(), vec!["a", "b"]);
+    assert_eq!(submitter.take(), vec!["a", "b"]);
+    assert!(submitter.is_empty());
+}